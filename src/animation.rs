@@ -0,0 +1,381 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use color_quant::NeuQuant;
+
+use crate::palette::Palette;
+
+// GIF colors per frame
+const GIF_COLORS: usize = 256;
+
+// Brightness steps of each palette color when quantising against a palette.
+// Rings fade out over black, so their colors mostly appear darkened.
+const PALETTE_SHADES: usize = 16;
+
+// Palette entries kept for shades of the palette colors; the rest are
+// fitted to the first frame so backgrounds still come out right
+const PALETTE_SHADE_BUDGET: usize = 160;
+
+// NeuQuant sampling factor: 1 is slowest and best, 30 fastest
+const NEUQUANT_SAMPLING: i32 = 10;
+
+// Bits kept per channel when caching nearest palette entries
+const LOOKUP_BITS: u32 = 5;
+
+// Where the colors of a GIF come from
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Quantization {
+    Adaptive,                               // Fitted to each frame with NeuQuant
+    Palette,                                // Shades of the active ring palette, shared by all frames
+}
+
+impl Quantization {
+    pub const ALL: [Quantization; 2] = [Quantization::Adaptive, Quantization::Palette];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Quantization::Adaptive => "adaptive",
+            Quantization::Palette => "palette",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Quantization> {
+        Quantization::ALL.iter().copied().find(|quantization| quantization.name().eq_ignore_ascii_case(name))
+    }
+}
+
+// Maps RGBA frames to palette indices, optionally with Floyd-Steinberg
+// error diffusion
+pub struct Quantizer {
+    colors: Vec<[u8; 3]>,
+    dither: bool,
+    lookup: Vec<u16>,                       // Nearest entry per reduced color, u16::MAX until needed
+}
+
+impl Quantizer {
+    pub fn new(colors: Vec<[u8; 3]>, dither: bool) -> Self {
+        let lookup = vec![u16::MAX; 1 << (3 * LOOKUP_BITS)];
+        Self { colors, dither, lookup }
+    }
+
+    // Up to 256 colors fitted to an RGBA image
+    pub fn fitted(rgba: &[u8], dither: bool) -> Self {
+        Self::new(fit_colors(rgba, GIF_COLORS), dither)
+    }
+
+    // Black, shades of every palette color and grays, topped up with colors
+    // fitted to `rgba`
+    pub fn for_palette(palette: &Palette, rgba: &[u8], dither: bool) -> Self {
+        let mut colors = vec![[0, 0, 0]];
+        let shades = (PALETTE_SHADE_BUDGET / palette.colors.len().max(1)).clamp(2, PALETTE_SHADES);
+        for color in palette.colors.iter().take(PALETTE_SHADE_BUDGET / 2) {
+            for step in 1..=shades {
+                let scale = step as f32 / shades as f32;
+                colors.push([color.red, color.green, color.blue].map(|channel| (channel as f32 * scale).round() as u8));
+            }
+        }
+        for step in 1..=PALETTE_SHADES {
+            colors.push([(255 * step / PALETTE_SHADES) as u8; 3]);
+        }
+        colors.dedup();
+        colors.truncate(GIF_COLORS);
+        let remaining = GIF_COLORS - colors.len();
+        if remaining > 0 {
+            colors.extend(fit_colors(rgba, remaining));
+        }
+        Self::new(colors, dither)
+    }
+
+    // Palette as packed RGB bytes, as GIF color tables are stored
+    pub fn color_table(&self) -> Vec<u8> {
+        self.colors.iter().flatten().copied().collect()
+    }
+
+    // Palette index for every pixel of a `width` x `height` RGBA image
+    pub fn quantize(&mut self, rgba: &[u8], width: usize, height: usize) -> Vec<u8> {
+        let mut indices = Vec::with_capacity(width * height);
+        if !self.dither {
+            for pixel in rgba.chunks_exact(4) {
+                indices.push(self.nearest([pixel[0], pixel[1], pixel[2]].map(f32::from)) as u8);
+            }
+            return indices;
+        }
+
+        // Floyd-Steinberg: push each pixel's error onto its unvisited neighbours
+        let mut row_error = vec![[0.0_f32; 3]; width + 2];
+        let mut next_error = vec![[0.0_f32; 3]; width + 2];
+        for y in 0..height {
+            for x in 0..width {
+                let pixel = &rgba[(y * width + x) * 4..][..3];
+                let wanted = [0, 1, 2].map(|c| (pixel[c] as f32 + row_error[x + 1][c]).clamp(0.0, 255.0));
+                let index = self.nearest(wanted);
+                let got = self.colors[index];
+                indices.push(index as u8);
+                for c in 0..3 {
+                    let error = wanted[c] - got[c] as f32;
+                    row_error[x + 2][c] += error * 7.0 / 16.0;
+                    next_error[x][c] += error * 3.0 / 16.0;
+                    next_error[x + 1][c] += error * 5.0 / 16.0;
+                    next_error[x + 2][c] += error / 16.0;
+                }
+            }
+            std::mem::swap(&mut row_error, &mut next_error);
+            next_error.fill([0.0; 3]);
+        }
+        indices
+    }
+
+    // Closest palette entry, cached per reduced color
+    fn nearest(&mut self, color: [f32; 3]) -> usize {
+        let shift = 8 - LOOKUP_BITS;
+        let [red, green, blue] = color.map(|channel| (channel.round() as u32).min(255) >> shift);
+        let key = ((red << (2 * LOOKUP_BITS)) | (green << LOOKUP_BITS) | blue) as usize;
+        if self.lookup[key] == u16::MAX {
+            // Search from the middle of the reduced cell
+            let half = (1 << shift) as f32 / 2.0;
+            let center = [red, green, blue].map(|channel| (channel << shift) as f32 + half);
+            self.lookup[key] = closest(&self.colors, center) as u16;
+        }
+        self.lookup[key] as usize
+    }
+}
+
+fn closest(colors: &[[u8; 3]], color: [f32; 3]) -> usize {
+    let distance = |entry: &[u8; 3]| (0..3).map(|c| (entry[c] as f32 - color[c]).powi(2)).sum::<f32>();
+    (0..colors.len()).min_by(|&a, &b| distance(&colors[a]).total_cmp(&distance(&colors[b]))).unwrap_or(0)
+}
+
+// NeuQuant palette of `count` colors for an RGBA image
+fn fit_colors(rgba: &[u8], count: usize) -> Vec<[u8; 3]> {
+    let quant = NeuQuant::new(NEUQUANT_SAMPLING, count, rgba);
+    quant.color_map_rgb().chunks_exact(3).map(|rgb| [rgb[0], rgb[1], rgb[2]]).collect()
+}
+
+// Turns a run of frames into a loop: the first `overlap` frames are held
+// back and blended into the last ones, so the end leads smoothly into the
+// start. Push `total` frames; `total - overlap` come out, beginning with
+// frame `overlap`.
+pub struct Seamless {
+    overlap: usize,
+    total: usize,
+    head: Vec<Vec<u8>>,                     // First frames, waiting for the tail
+    pushed: usize,
+}
+
+impl Seamless {
+    pub fn new(total: usize, overlap: usize) -> Self {
+        Self { overlap: overlap.min(total / 2), total, head: Vec::new(), pushed: 0 }
+    }
+
+    // Frames that come out of `total` pushed ones
+    pub fn output_len(&self) -> usize {
+        self.total - self.overlap
+    }
+
+    // Take the next RGBA frame, returning the frame to show in its place, if any
+    pub fn push(&mut self, frame: &[u8]) -> Option<Vec<u8>> {
+        let index = self.pushed;
+        self.pushed += 1;
+        if index < self.overlap {
+            self.head.push(frame.to_vec());
+            return None;
+        }
+        let tail_start = self.total - self.overlap;
+        if index < tail_start {
+            return Some(frame.to_vec());
+        }
+        // Fade from the tail to the held head frame that follows on from it
+        let head = self.head.get(index - tail_start)?;
+        let weight = (index - tail_start + 1) as f32 / (self.overlap + 1) as f32;
+        Some(frame.iter().zip(head).map(|(&tail, &head)| (tail as f32 + (head as f32 - tail as f32) * weight).round() as u8).collect())
+    }
+}
+
+// Frame delays in hundredths of a second. GIF delays are whole
+// hundredths, so they alternate where needed to keep the overall rate.
+fn gif_delay(frame: usize, fps: u32) -> u16 {
+    let at = |frame: usize| (frame as f64 * 100.0 / fps as f64).round() as u64;
+    (at(frame + 1) - at(frame)) as u16
+}
+
+// Writes an endlessly looping animated GIF, one frame at a time
+pub struct GifWriter {
+    encoder: gif::Encoder<BufWriter<File>>,
+    width: u16,
+    height: u16,
+    fps: u32,
+    quantization: Quantization,
+    dither: bool,
+    palette: Palette,
+    quantizer: Option<Quantizer>,           // Shared palette, built from the first frame
+    frame: usize,
+}
+
+impl GifWriter {
+    pub fn create(path: &Path, width: u32, height: u32, fps: u32, quantization: Quantization, palette: &Palette, dither: bool) -> Result<Self, String> {
+        let context = |e: String| format!("{}: {}", path.display(), e);
+        let (width, height) = (u16::try_from(width), u16::try_from(height));
+        let (Ok(width), Ok(height)) = (width, height) else {
+            return Err(context("GIF images are at most 65535 pixels across".to_string()));
+        };
+        let file = File::create(path).map_err(|e| context(e.to_string()))?;
+        let mut encoder = gif::Encoder::new(BufWriter::new(file), width, height, &[]).map_err(|e| context(e.to_string()))?;
+        encoder.set_repeat(gif::Repeat::Infinite).map_err(|e| context(e.to_string()))?;
+        Ok(Self { encoder, width, height, fps, quantization, dither, palette: palette.clone(), quantizer: None, frame: 0 })
+    }
+
+    // Add an RGBA frame
+    pub fn write(&mut self, rgba: &[u8]) -> Result<(), String> {
+        let (width, height) = (self.width as usize, self.height as usize);
+        let mut quantizer = match (self.quantization, self.quantizer.take()) {
+            (Quantization::Adaptive, _) => Quantizer::fitted(rgba, self.dither),
+            (Quantization::Palette, Some(quantizer)) => quantizer,
+            (Quantization::Palette, None) => Quantizer::for_palette(&self.palette, rgba, self.dither),
+        };
+        let indices = quantizer.quantize(rgba, width, height);
+        let frame = gif::Frame {
+            width: self.width,
+            height: self.height,
+            delay: gif_delay(self.frame, self.fps),
+            palette: Some(quantizer.color_table()),
+            buffer: indices.into(),
+            ..gif::Frame::default()
+        };
+        self.encoder.write_frame(&frame).map_err(|e| e.to_string())?;
+        if self.quantization == Quantization::Palette {
+            self.quantizer = Some(quantizer);
+        }
+        self.frame += 1;
+        Ok(())
+    }
+
+    // Write the end of the file
+    pub fn finish(self) -> Result<(), String> {
+        let mut file = self.encoder.into_inner().map_err(|e| e.to_string())?;
+        file.flush().map_err(|e| e.to_string())
+    }
+}
+
+// Save one RGBA frame as a PNG file
+pub fn save_png(path: &Path, width: u32, height: u32, rgba: &[u8]) -> Result<(), String> {
+    let context = |e: png::EncodingError| format!("{}: {}", path.display(), e);
+    let file = File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(context)?;
+    writer.write_image_data(rgba).map_err(context)?;
+    writer.finish().map_err(context)
+}
+
+// Writes an endlessly looping APNG. The frame count has to be known up front.
+pub struct ApngWriter {
+    writer: png::Writer<BufWriter<File>>,
+}
+
+impl ApngWriter {
+    pub fn create(path: &Path, width: u32, height: u32, fps: u32, frames: u32) -> Result<Self, String> {
+        let context = |e: png::EncodingError| format!("{}: {}", path.display(), e);
+        let file = File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(frames.max(1), 0).map_err(context)?;
+        encoder.set_frame_delay(1, fps.min(u16::MAX as u32) as u16).map_err(context)?;
+        let writer = encoder.write_header().map_err(context)?;
+        Ok(Self { writer })
+    }
+
+    // Add an RGBA frame
+    pub fn write(&mut self, rgba: &[u8]) -> Result<(), String> {
+        self.writer.write_image_data(rgba).map_err(|e| e.to_string())
+    }
+
+    pub fn finish(self) -> Result<(), String> {
+        self.writer.finish().map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A `width` x `height` RGBA gradient with many distinct colors
+    fn gradient(width: usize, height: usize) -> Vec<u8> {
+        (0..width * height).flat_map(|i| [(i % width * 255 / width) as u8, (i / width * 255 / height) as u8, (i * 7 % 256) as u8, 255]).collect()
+    }
+
+    // Path in the temporary directory, unique to this test run
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("waves-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn quantizers_fit_a_gif_color_table() {
+        let rgba = gradient(32, 32);
+        for palette in crate::palette::builtin() {
+            let mut quantizer = Quantizer::for_palette(&palette, &rgba, true);
+            assert_eq!(quantizer.color_table().len(), GIF_COLORS * 3, "{}", palette.name);
+            assert!(quantizer.color_table().starts_with(&[0, 0, 0]));
+            assert_eq!(quantizer.quantize(&rgba, 32, 32).len(), 32 * 32);
+        }
+        assert!(Quantizer::fitted(&rgba, false).color_table().len() <= GIF_COLORS * 3);
+    }
+
+    #[test]
+    fn seamless_loops_blend_the_end_into_the_start() {
+        let mut seamless = Seamless::new(10, 3);
+        let frames: Vec<Vec<u8>> = (0..10).filter_map(|i| seamless.push(&[i * 10])).collect();
+        assert_eq!(frames.len(), seamless.output_len());
+        // Starts at frame 3 and fades towards frames 0 to 2 as it wraps back round
+        assert_eq!(frames.concat(), [30, 40, 50, 60, 53, 45, 38]);
+    }
+
+    #[test]
+    fn gif_delays_keep_the_frame_rate() {
+        for fps in [24, 30, 60] {
+            let delays: Vec<u16> = (0..fps as usize).map(|frame| gif_delay(frame, fps)).collect();
+            assert_eq!(delays.iter().sum::<u16>(), 100, "{} fps", fps);
+            assert!(delays.iter().all(|&delay| delay.abs_diff(delays[0]) <= 1));
+        }
+    }
+
+    #[test]
+    fn animations_read_back_with_their_frames() {
+        let (width, height, frames) = (16, 8, 3);
+        let rgba = gradient(width as usize, height as usize);
+
+        let path = temp_path("loop.gif");
+        let mut gif = GifWriter::create(&path, width, height, 30, Quantization::Palette, &crate::palette::builtin()[0], false).unwrap();
+        for _ in 0..frames {
+            gif.write(&rgba).unwrap();
+        }
+        gif.finish().unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(bytes.starts_with(b"GIF89a"));
+        assert!(bytes.windows(11).any(|window| window == b"NETSCAPE2.0"));
+        let mut decoder = gif::DecodeOptions::new().read_info(bytes.as_slice()).unwrap();
+        assert_eq!((decoder.width(), decoder.height()), (width as u16, height as u16));
+        let mut count = 0;
+        while decoder.read_next_frame().unwrap().is_some() {
+            count += 1;
+        }
+        assert_eq!(count, frames);
+
+        let path = temp_path("loop.png");
+        let mut apng = ApngWriter::create(&path, width, height, 30, frames).unwrap();
+        for _ in 0..frames {
+            apng.write(&rgba).unwrap();
+        }
+        apng.finish().unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let reader = png::Decoder::new(bytes.as_slice()).read_info().unwrap();
+        let info = reader.info();
+        assert_eq!((info.width, info.height), (width, height));
+        let control = info.animation_control.unwrap();
+        assert_eq!((control.num_frames, control.num_plays), (frames, 0));
+    }
+}
@@ -0,0 +1,256 @@
+use rand::Rng;
+
+use crate::emitter::Spawn;
+use crate::geom::{Bounds, Point};
+use crate::rain::Rain;
+use crate::ripple::RippleKind;
+
+// Attract mode settings
+pub const IDLE_TIMEOUT: f32 = 60.0;         // Seconds without input before attract mode starts
+pub const PATTERN_DURATION: f32 = 20.0;     // Seconds each pattern runs before the next one
+pub const ATTRACT_RAIN_RATE: f32 = 6.0;     // Drops per second in the rain pattern
+pub const SPIRAL_INTERVAL: f32 = 0.08;      // Seconds between ripples along a spiral
+pub const SPIRAL_TURN: f32 = 0.45;          // Radians turned between consecutive ripples
+pub const SPIRAL_ARMS: u32 = 2;             // Arms wound around the center at once
+pub const SPIRAL_STEPS: u32 = 60;           // Ripples from the center to the edge of one arm
+pub const LISSAJOUS_INTERVAL: f32 = 0.12;   // Seconds between ripples from each Lissajous emitter
+pub const LISSAJOUS_SPEED: f32 = 0.35;      // Base angular speed of the emitters, radians per second
+pub const LISSAJOUS_RATIOS: [(f32, f32); 2] = [(3.0, 2.0), (2.0, 3.0)]; // Horizontal and vertical frequency of each emitter
+pub const PATTERN_MARGIN: f32 = 0.8;        // Share of the half window the patterns reach out to
+pub const MAX_TICKS_PER_UPDATE: usize = 8;  // Cap on catch-up spawn ticks after a slow frame
+
+// Scripted ripple pattern shown while nobody is around
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Pattern {
+    #[default]
+    Rain,                                   // Random drops all over the window
+    Spiral,                                 // Ripples winding out from the center
+    Lissajous,                              // Emitters tracing Lissajous figures
+}
+
+impl Pattern {
+    pub const ALL: [Pattern; 3] = [Pattern::Rain, Pattern::Spiral, Pattern::Lissajous];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Pattern::Rain => "RAIN",
+            Pattern::Spiral => "SPIRAL",
+            Pattern::Lissajous => "LISSAJOUS",
+        }
+    }
+
+    pub fn next(self) -> Pattern {
+        let index = Pattern::ALL.iter().position(|&pattern| pattern == self).unwrap_or(0);
+        Pattern::ALL[(index + 1) % Pattern::ALL.len()]
+    }
+}
+
+// Screensaver that takes over after a stretch without input, cycling
+// through scripted patterns until the next real input
+#[derive(Clone, Debug)]
+pub struct Attract {
+    pub enabled: bool,
+    pub timeout: f32,                       // Seconds without input before it starts
+    pub pattern_duration: f32,              // Seconds per pattern
+    idle: f32,                              // Seconds since the last input
+    active: bool,                           // Whether attract mode is running
+    switched: bool,                         // Whether the last update started a pattern
+    pattern: Pattern,                       // Pattern currently shown
+    elapsed: f32,                           // Seconds into the current pattern
+    since_spawn: f32,                       // Seconds since the pattern last spawned a ripple
+    step: u32,                              // Spawn ticks of the current pattern so far
+    rain: Rain,                             // Drop source for the rain pattern
+}
+
+impl Default for Attract {
+    fn default() -> Self {
+        let mut rain = Rain::new();
+        rain.enabled = true;
+        rain.rate = ATTRACT_RAIN_RATE;
+        Self {
+            enabled: true,
+            timeout: IDLE_TIMEOUT,
+            pattern_duration: PATTERN_DURATION,
+            idle: 0.0,
+            active: false,
+            switched: false,
+            pattern: Pattern::default(),
+            elapsed: 0.0,
+            since_spawn: 0.0,
+            step: 0,
+            rain,
+        }
+    }
+}
+
+impl Attract {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn pattern(&self) -> Pattern {
+        self.pattern
+    }
+
+    // Whether the last update started attract mode or moved to the next pattern
+    pub fn switched(&self) -> bool {
+        self.switched
+    }
+
+    // Report real input. Restarts the idle timer and stops attract mode;
+    // returns true if it was running.
+    pub fn input(&mut self) -> bool {
+        self.idle = 0.0;
+        let was_active = self.active;
+        self.active = false;
+        self.switched = false;
+        was_active
+    }
+
+    // Advance by `dt` seconds. Returns the ripples the current pattern
+    // spawns inside `bounds`; nothing until the idle timeout has passed.
+    pub fn update(&mut self, dt: f32, bounds: Bounds, rng: &mut impl Rng) -> Vec<Spawn> {
+        self.switched = false;
+        self.idle += dt;
+        if !self.active {
+            if !self.enabled || self.idle < self.timeout {
+                return Vec::new();
+            }
+            self.active = true;
+            self.start(Pattern::default());
+        } else {
+            self.elapsed += dt;
+            if self.elapsed >= self.pattern_duration {
+                self.start(self.pattern.next());
+            }
+        }
+
+        match self.pattern {
+            Pattern::Rain => self.rain.update(dt, bounds, rng),
+            Pattern::Spiral => self.spiral(dt, bounds),
+            Pattern::Lissajous => self.lissajous(dt, bounds),
+        }
+    }
+
+    fn start(&mut self, pattern: Pattern) {
+        self.pattern = pattern;
+        self.switched = true;
+        self.elapsed = 0.0;
+        self.since_spawn = 0.0;
+        self.step = 0;
+    }
+
+    // Ripples placed at a steady pace along arms winding out from the center,
+    // starting over from the middle once the arms reach the edge
+    fn spiral(&mut self, dt: f32, bounds: Bounds) -> Vec<Spawn> {
+        let center = bounds.center();
+        let reach = bounds.width().min(bounds.height()) * 0.5 * PATTERN_MARGIN;
+        let mut spawns = Vec::new();
+        self.since_spawn = (self.since_spawn + dt).min(SPIRAL_INTERVAL * MAX_TICKS_PER_UPDATE as f32);
+        while self.since_spawn >= SPIRAL_INTERVAL {
+            self.since_spawn -= SPIRAL_INTERVAL;
+            let along = (self.step % SPIRAL_STEPS) as f32 / SPIRAL_STEPS as f32;
+            let arm = (self.step / SPIRAL_STEPS) % SPIRAL_ARMS;
+            let angle = self.step as f32 * SPIRAL_TURN + arm as f32 * std::f32::consts::TAU / SPIRAL_ARMS as f32;
+            let position = Point::new(center.x + angle.cos() * reach * along, center.y + angle.sin() * reach * along);
+            let mut spawn = Spawn::new(position, RippleKind::Droplet);
+            spawn.strength = 0.6 + along;
+            spawns.push(spawn);
+            self.step += 1;
+        }
+        spawns
+    }
+
+    // Emitters moving along Lissajous curves, each dropping a trail of ripples
+    fn lissajous(&mut self, dt: f32, bounds: Bounds) -> Vec<Spawn> {
+        let center = bounds.center();
+        let (half_width, half_height) = (bounds.width() * 0.5 * PATTERN_MARGIN, bounds.height() * 0.5 * PATTERN_MARGIN);
+        let mut spawns = Vec::new();
+        self.since_spawn = (self.since_spawn + dt).min(LISSAJOUS_INTERVAL * MAX_TICKS_PER_UPDATE as f32);
+        while self.since_spawn >= LISSAJOUS_INTERVAL {
+            self.since_spawn -= LISSAJOUS_INTERVAL;
+            let time = self.step as f32 * LISSAJOUS_INTERVAL * LISSAJOUS_SPEED;
+            for (i, &(a, b)) in LISSAJOUS_RATIOS.iter().enumerate() {
+                // Quarter turn phase shifts keep the figures open and apart
+                let phase = std::f32::consts::FRAC_PI_2 * (i + 1) as f32;
+                let position = Point::new(
+                    center.x + (a * time + phase).sin() * half_width,
+                    center.y + (b * time).sin() * half_height,
+                );
+                spawns.push(Spawn::new(position, RippleKind::Drop));
+            }
+            self.step += 1;
+        }
+        spawns
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNDS: Bounds = Bounds::new(-200.0, 200.0, -150.0, 150.0);
+
+    // Attract mode already running its first pattern
+    fn attract(rng: &mut impl Rng) -> Attract {
+        let mut attract = Attract::new();
+        attract.update(IDLE_TIMEOUT, BOUNDS, rng);
+        attract
+    }
+
+    #[test]
+    fn starts_after_the_timeout_and_stops_on_input() {
+        let mut attract = Attract::new();
+        let mut rng = crate::random::seeded(1);
+        assert!(attract.update(IDLE_TIMEOUT - 1.0, BOUNDS, &mut rng).is_empty());
+        assert!(!attract.is_active());
+        attract.update(1.0, BOUNDS, &mut rng);
+        assert!(attract.is_active() && attract.switched());
+        assert_eq!(attract.pattern(), Pattern::Rain);
+        assert!(attract.input());
+        assert!(!attract.is_active() && !attract.input());
+    }
+
+    #[test]
+    fn patterns_take_turns() {
+        let mut rng = crate::random::seeded(1);
+        let mut attract = attract(&mut rng);
+        let mut shown = vec![attract.pattern()];
+        for _ in 0..(PATTERN_DURATION * 3.0 * 10.0) as usize {
+            attract.update(0.1, BOUNDS, &mut rng);
+            if attract.switched() {
+                shown.push(attract.pattern());
+            }
+        }
+        assert_eq!(shown, [Pattern::Rain, Pattern::Spiral, Pattern::Lissajous, Pattern::Rain]);
+    }
+
+    #[test]
+    fn spirals_and_figures_spawn_at_their_interval() {
+        let mut rng = crate::random::seeded(1);
+        for (pattern, interval, per_tick) in [(Pattern::Spiral, SPIRAL_INTERVAL, 1), (Pattern::Lissajous, LISSAJOUS_INTERVAL, 2)] {
+            let mut attract = attract(&mut rng);
+            attract.start(pattern);
+            let count: usize = (0..600).map(|_| attract.update(1.0 / 60.0, BOUNDS, &mut rng).len()).sum();
+            let expected = (10.0 / interval) as usize * per_tick;
+            assert!(count.abs_diff(expected) <= per_tick, "{:?}: {} spawns", pattern, count);
+        }
+    }
+
+    #[test]
+    fn a_slow_frame_spawns_at_most_the_cap() {
+        let mut rng = crate::random::seeded(1);
+        for (pattern, per_tick) in [(Pattern::Spiral, 1), (Pattern::Lissajous, 2)] {
+            let mut attract = attract(&mut rng);
+            attract.start(pattern);
+            let count = attract.update(5.0, BOUNDS, &mut rng).len();
+            assert!(count > 0 && count <= MAX_TICKS_PER_UPDATE * per_tick, "{:?}: {} spawns", pattern, count);
+            // The backlog is gone rather than arriving on the next frames
+            assert!(attract.update(1.0 / 60.0, BOUNDS, &mut rng).len() <= per_tick);
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use noise::{NoiseFn, Perlin};
+
+use crate::color::{self, Interpolation};
+use crate::geom::{Bounds, Point};
+use crate::random::{self, SimRng};
+use crate::scene::Primitive;
+
+// Water effect settings
+const WATER_DETAIL_LEVELS: usize = 4;  // How many octaves of noise
+const WATER_ANIMATION_SPEED: f32 = 0.15;
+
+// Lava effect settings
+const LAVA_DETAIL_LEVELS: usize = 3;  // Fewer octaves for chunkier look
+const LAVA_ANIMATION_SPEED: f32 = 0.05; // Slower movement for lava
+
+// Radial background settings
+const RADIAL_ANIMATION_SPEED: f32 = 0.1;
+const RADIAL_COLOR_SPEED: f32 = 0.2;
+const RADIAL_RAYS: usize = 300;
+
+// Animated backdrop drawn behind the ripples
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum BackgroundType {
+    None,
+    Water,
+    Lava,
+    Radial,
+}
+
+impl BackgroundType {
+    pub const ALL: [BackgroundType; 4] = [BackgroundType::None, BackgroundType::Water, BackgroundType::Lava, BackgroundType::Radial];
+
+    // Name used by timeline files and the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            BackgroundType::None => "none",
+            BackgroundType::Water => "water",
+            BackgroundType::Lava => "lava",
+            BackgroundType::Radial => "radial",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<BackgroundType> {
+        BackgroundType::ALL.iter().copied().find(|background| background.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn next(self) -> BackgroundType {
+        match self {
+            BackgroundType::None => BackgroundType::Water,
+            BackgroundType::Water => BackgroundType::Lava,
+            BackgroundType::Lava => BackgroundType::Radial,
+            BackgroundType::Radial => BackgroundType::None,
+        }
+    }
+}
+
+// Add the background of the given type. `alpha` below 1 lays it over what
+// is already drawn as a translucent layer (trails mode, crossfades).
+pub fn draw(out: &mut Vec<Primitive>, background: BackgroundType, bounds: Bounds, noise: &Perlin, time: f32, alpha: f32, rng: &mut SimRng) {
+    match background {
+        BackgroundType::Water => water(out, bounds, noise, time, alpha, rng),
+        BackgroundType::Lava => lava(out, bounds, noise, time, alpha, rng),
+        BackgroundType::Radial => radial(out, bounds, noise, time, alpha),
+        BackgroundType::None => {}, // No background
+    }
+}
+
+// Linear interpolation between two colors
+fn lerp(start: [f32; 3], end: [f32; 3], percent: f32) -> [f32; 3] {
+    color::lerp(start, end, percent, Interpolation::Srgb)
+}
+
+// Horizontal line across the whole of `bounds`
+fn row(bounds: Bounds, y: f32, color: [f32; 4]) -> Primitive {
+    Primitive::Line { start: Point::new(bounds.left, y), end: Point::new(bounds.right, y), weight: 1.0, color }
+}
+
+// Small filled circle
+fn dot(x: f32, y: f32, size: f32, color: [f32; 4]) -> Primitive {
+    Primitive::Ellipse { center: Point::new(x, y), width: size, height: size, color }
+}
+
+// The animated water background
+pub fn water(out: &mut Vec<Primitive>, bounds: Bounds, noise: &Perlin, time: f32, alpha: f32, rng: &mut SimRng) {
+    let resolution = 3; // Draw every Nth row for performance
+
+    // Define colors
+    let water_surface_color = [0.0, 0.3, 0.6];
+    let water_deep_color = [0.0, 0.05, 0.2];
+
+    for y in (bounds.bottom as i32..bounds.top as i32).step_by(resolution) {
+        let depth_factor = (y as f32 - bounds.bottom) / bounds.height();
+        let [red, green, blue] = lerp(water_deep_color, water_surface_color, depth_factor);
+
+        // Multi-octave noise for more natural water appearance
+        let noise_value = (0..WATER_DETAIL_LEVELS).fold(0.0, |acc, i| {
+            let amplitude = 0.5_f32.powi(i as i32);
+            let scale = 0.01 * (i + 1) as f64;
+
+            acc + amplitude * noise.get([
+                time as f64 * WATER_ANIMATION_SPEED as f64 * (i + 1) as f64,
+                y as f64 * scale,
+                time as f64 * 0.05
+            ]) as f32
+        });
+
+        // Apply light shimmering effect
+        let light_factor = 1.0 + noise_value * 0.2;
+        let color = [
+            (red * light_factor).min(1.0),
+            (green * light_factor).min(1.0),
+            (blue * light_factor).min(1.0),
+            alpha,
+        ];
+
+        // Water line
+        out.push(row(bounds, y as f32, color));
+    }
+
+    // Adds some highlight specks on the water surface for extra effect
+    let speck_count = 500;
+
+    for _ in 0..speck_count {
+        let x = random::range(rng, bounds.left, bounds.right);
+        let y = random::range(rng, bounds.bottom, bounds.top);
+
+        // Use noise to determine visibility of speck (makes them flicker)
+        let noise_val = noise.get([
+            x as f64 * 0.01,
+            y as f64 * 0.01,
+            time as f64 * 0.5
+        ]) as f32;
+
+        if noise_val > 0.7 {
+            let size = random::range(rng, 1.0, 3.0);
+            let brightness = random::range(rng, 0.7, 1.0);
+
+            out.push(dot(x, y, size, [brightness, brightness, brightness, 0.6 * alpha]));
+        }
+    }
+}
+
+// The animated lava background
+pub fn lava(out: &mut Vec<Primitive>, bounds: Bounds, noise: &Perlin, time: f32, alpha: f32, rng: &mut SimRng) {
+    let resolution = 4; // Slightly chunkier resolution for lava
+
+    // Define colors
+    let lava_surface_color = [0.9, 0.3, 0.0];  // Bright orange-red
+    let lava_deep_color = [0.4, 0.0, 0.0];     // Dark red
+
+    for y in (bounds.bottom as i32..bounds.top as i32).step_by(resolution) {
+        let depth_factor = (y as f32 - bounds.bottom) / bounds.height();
+        let [red, green, blue] = lerp(lava_deep_color, lava_surface_color, depth_factor);
+
+        // Multi-octave noise for bubbling lava appearance
+        let noise_value = (0..LAVA_DETAIL_LEVELS).fold(0.0, |acc, i| {
+            let amplitude = 0.6_f32.powi(i as i32); // Higher amplitude for more contrast
+            let scale = 0.008 * (i + 1) as f64;     // Larger features
+
+            acc + amplitude * noise.get([
+                time as f64 * LAVA_ANIMATION_SPEED as f64 * (i + 1) as f64,
+                y as f64 * scale,
+                time as f64 * 0.03
+            ]) as f32
+        });
+
+        // Apply glowing/bubbling effect
+        let glow_factor = 1.0 + noise_value * 0.4; // More intense variation
+        let color = [
+            (red * glow_factor).min(1.0),
+            (green * glow_factor).min(1.0),
+            (blue * glow_factor).min(1.0),
+            alpha,
+        ];
+
+        // Lava line
+        out.push(row(bounds, y as f32, color));
+    }
+
+    // Add bubbles and sparks to the lava
+    let bubble_count = 300;
+
+    for _ in 0..bubble_count {
+        let x = random::range(rng, bounds.left, bounds.right);
+        let y = random::range(rng, bounds.bottom, bounds.top);
+
+        // Use noise to determine visibility (bubbling effect)
+        let noise_val = noise.get([
+            x as f64 * 0.015,
+            y as f64 * 0.015,
+            time as f64 * 0.2
+        ]) as f32;
+
+        if noise_val > 0.65 {
+            let size = random::range(rng, 1.0, 5.0);
+
+            // Brighter at the top (rising heat)
+            let y_factor = (y - bounds.bottom) / bounds.height();
+            let brightness = 0.7 + y_factor * 0.3;
+
+            // Yellow-orange glow
+            out.push(dot(x, y, size, [brightness, brightness * 0.6, 0.0, 0.7 * alpha]));
+        }
+    }
+
+    // Add a few bright sparks
+    let spark_count = 50;
+    for _ in 0..spark_count {
+        let x = random::range(rng, bounds.left, bounds.right);
+        let y = random::range(rng, bounds.bottom, bounds.top);
+
+        let noise_val = noise.get([
+            x as f64 * 0.02,
+            y as f64 * 0.02,
+            time as f64 * 1.5
+        ]) as f32;
+
+        if noise_val > 0.8 {
+            let size = random::range(rng, 1.0, 2.5);
+
+            out.push(dot(x, y, size, [1.0, 1.0, 0.3, 0.9 * alpha])); // Bright yellow spark
+        }
+    }
+}
+
+// Rays of shifting color fanning out from the center
+pub fn radial(out: &mut Vec<Primitive>, bounds: Bounds, noise: &Perlin, time: f32, alpha: f32) {
+    let center = bounds.center();
+
+    // Rays emanating from center
+    for i in 0..RADIAL_RAYS {
+        let angle = (i as f32 / RADIAL_RAYS as f32) * std::f32::consts::TAU;
+        let ray_length = bounds.width().max(bounds.height());
+
+        // Use noise to create dynamic colors
+        let noise_val1 = noise.get([
+            angle as f64 * 0.5,
+            time as f64 * RADIAL_COLOR_SPEED as f64,
+            0.0
+        ]) as f32;
+
+        let noise_val2 = noise.get([
+            angle as f64 * 0.5,
+            time as f64 * RADIAL_COLOR_SPEED as f64,
+            1.0
+        ]) as f32;
+
+        // Create vibrant colors that shift over time
+        let r = 0.5 + 0.5 * (noise_val1 * 3.0).sin();
+        let g = 0.5 + 0.5 * (noise_val2 * 2.5 + 1.0).sin();
+        let b = 0.5 + 0.5 * (noise_val1 * 2.0 + 2.0).sin();
+
+        // Make the center brighter
+        let center_brightness = 0.7 + 0.3 * (time * RADIAL_ANIMATION_SPEED + angle).sin();
+
+        // Instead of using gradient lines, we'll draw multiple segments with decreasing opacity
+        let segments = 15;
+        for j in 0..segments {
+            let t_start = j as f32 / segments as f32;
+            let t_end = (j + 1) as f32 / segments as f32;
+
+            let start = Point::new(center.x + angle.cos() * ray_length * t_start, center.y + angle.sin() * ray_length * t_start);
+            let end = Point::new(center.x + angle.cos() * ray_length * t_end, center.y + angle.sin() * ray_length * t_end);
+
+            // Calculate opacity based on distance from center
+            let opacity = alpha * if j == 0 {
+                0.9 // Brightest at center
+            } else {
+                0.7 * (1.0 - t_start) // Fade out toward edges
+            };
+
+            // Adjust color based on segment position
+            let segment_color = if j == 0 {
+                // Brighter at center
+                [
+                    (r * center_brightness).min(1.0),
+                    (g * center_brightness).min(1.0),
+                    (b * center_brightness).min(1.0),
+                    opacity
+                ]
+            } else {
+                [r, g, b, opacity]
+            };
+
+            // Line weight varies with angle and time for organic feel
+            let weight = 3.0 + 2.0 * (time * 0.5 + angle * 0.2).sin();
+
+            out.push(Primitive::Line { start, end, weight, color: segment_color });
+        }
+    }
+}
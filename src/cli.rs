@@ -0,0 +1,172 @@
+use std::path::PathBuf;
+
+use waves::{BackgroundStyle, Quantization};
+
+// Command line options
+pub struct Args {
+    pub obstacles: Option<PathBuf>,     // Obstacle file to load at startup
+    pub seed: Option<u64>,              // Seed for all randomness (random if not given)
+    pub shape_path: Option<String>,     // SVG path data for a custom ripple shape
+    pub record: Option<PathBuf>,        // Session file to record input to
+    pub replay: Option<PathBuf>,        // Session file to play back
+    pub replay_speed: f32,              // Rate the replayed session's events come at
+    pub loop_replay: bool,              // Start the replayed session over when it ends
+    pub timeline: Option<PathBuf>,      // Timeline file to play
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            obstacles: None,
+            seed: None,
+            shape_path: None,
+            record: None,
+            replay: None,
+            replay_speed: 1.0,
+            loop_replay: false,
+            timeline: None,
+        }
+    }
+}
+
+impl Args {
+    pub fn parse() -> Self {
+        let mut args = Args::default();
+        let mut iter = std::env::args().skip(1);
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--obstacles" => args.obstacles = iter.next().map(PathBuf::from),
+                "--seed" => match iter.next().map(|value| value.parse::<u64>()) {
+                    Some(Ok(seed)) => args.seed = Some(seed),
+                    _ => eprintln!("--seed expects a whole number"),
+                },
+                "--shape-path" => args.shape_path = iter.next(),
+                "--record" => args.record = iter.next().map(PathBuf::from),
+                "--replay" => args.replay = iter.next().map(PathBuf::from),
+                "--replay-speed" => match iter.next().map(|value| value.parse::<f32>()) {
+                    Some(Ok(speed)) if speed > 0.0 => args.replay_speed = speed,
+                    _ => eprintln!("--replay-speed expects a positive number"),
+                },
+                "--loop" => args.loop_replay = true,
+                "--timeline" => args.timeline = iter.next().map(PathBuf::from),
+                other => eprintln!("Ignoring unknown argument '{}'", other),
+            }
+        }
+        args
+    }
+}
+
+// Options of `waves render`, which draws frames to files without a window
+pub struct RenderArgs {
+    pub duration: f32,                  // Seconds of simulation to render
+    pub fps: u32,                       // Frames per simulated second
+    pub width: u32,                     // Image size in pixels
+    pub height: u32,
+    pub seed: Option<u64>,              // Seed for all randomness (the replay's, or random, if not given)
+    pub replay: Option<PathBuf>,        // Session file driving the render
+    pub replay_speed: f32,
+    pub loop_replay: bool,
+    pub timeline: Option<PathBuf>,      // Timeline file driving the render
+    pub background: Option<String>,     // Background to start with, by name
+    pub palette: Option<String>,        // Ring palette to start with, by name
+    pub output: PathBuf,                // Frame directory, or a .gif, .png (APNG) or .svg file
+    pub quantization: Quantization,     // Where GIF colors come from
+    pub dither: bool,                   // Dither GIF frames
+    pub seamless: f32,                  // Seconds of the end crossfaded into the start, 0 for none
+    pub svg_background: BackgroundStyle, // How an SVG holds the background
+}
+
+impl Default for RenderArgs {
+    fn default() -> Self {
+        Self {
+            duration: 10.0,
+            fps: 30,
+            width: 1280,
+            height: 720,
+            seed: None,
+            replay: None,
+            replay_speed: 1.0,
+            loop_replay: false,
+            timeline: None,
+            background: None,
+            palette: None,
+            output: PathBuf::from("frames"),
+            quantization: Quantization::Adaptive,
+            dither: true,
+            seamless: 0.0,
+            svg_background: BackgroundStyle::Gradient,
+        }
+    }
+}
+
+impl RenderArgs {
+    // Parse the arguments after `render`
+    pub fn parse() -> Self {
+        let mut args = RenderArgs::default();
+        let mut iter = std::env::args().skip(2);
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--duration" => match iter.next().map(|value| value.parse::<f32>()) {
+                    Some(Ok(duration)) if duration > 0.0 => args.duration = duration,
+                    _ => eprintln!("--duration expects a positive number of seconds"),
+                },
+                "--fps" => match iter.next().map(|value| value.parse::<u32>()) {
+                    Some(Ok(fps)) if fps > 0 => args.fps = fps,
+                    _ => eprintln!("--fps expects a positive whole number"),
+                },
+                "--size" => match iter.next().as_deref().and_then(parse_size) {
+                    Some((width, height)) => (args.width, args.height) = (width, height),
+                    None => eprintln!("--size expects WIDTHxHEIGHT, e.g. 1280x720"),
+                },
+                "--seed" => match iter.next().map(|value| value.parse::<u64>()) {
+                    Some(Ok(seed)) => args.seed = Some(seed),
+                    _ => eprintln!("--seed expects a whole number"),
+                },
+                "--replay" => args.replay = iter.next().map(PathBuf::from),
+                "--replay-speed" => match iter.next().map(|value| value.parse::<f32>()) {
+                    Some(Ok(speed)) if speed > 0.0 => args.replay_speed = speed,
+                    _ => eprintln!("--replay-speed expects a positive number"),
+                },
+                "--loop" => args.loop_replay = true,
+                "--timeline" => args.timeline = iter.next().map(PathBuf::from),
+                "--background" => args.background = iter.next(),
+                "--palette" => args.palette = iter.next(),
+                "--out" => if let Some(path) = iter.next() {
+                    args.output = PathBuf::from(path);
+                },
+                "--quantize" => match iter.next().as_deref().and_then(Quantization::from_name) {
+                    Some(quantization) => args.quantization = quantization,
+                    None => eprintln!("--quantize expects adaptive or palette"),
+                },
+                "--no-dither" => args.dither = false,
+                "--seamless" => match iter.next().map(|value| value.parse::<f32>()) {
+                    Some(Ok(seconds)) if seconds >= 0.0 => args.seamless = seconds,
+                    _ => eprintln!("--seamless expects a number of seconds"),
+                },
+                "--svg-background" => match iter.next().as_deref().and_then(BackgroundStyle::from_name) {
+                    Some(style) => args.svg_background = style,
+                    None => eprintln!("--svg-background expects gradient or raster"),
+                },
+                other => eprintln!("Ignoring unknown argument '{}'", other),
+            }
+        }
+        args
+    }
+
+    // Number of frames the render produces
+    pub fn frame_count(&self) -> u32 {
+        (self.duration * self.fps as f32).round() as u32
+    }
+
+    // Extra frames rendered past the end to crossfade into the start
+    pub fn seamless_frames(&self) -> u32 {
+        ((self.seamless * self.fps as f32).round() as u32).min(self.frame_count())
+    }
+}
+
+// "1280x720" as (1280, 720)
+fn parse_size(text: &str) -> Option<(u32, u32)> {
+    let (width, height) = text.split_once(['x', 'X'])?;
+    let (width, height) = (width.trim().parse().ok()?, height.trim().parse().ok()?);
+    (width > 0 && height > 0).then_some((width, height))
+}
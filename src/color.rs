@@ -0,0 +1,186 @@
+// 8-bit sRGB color, laid out like nannou's `rgb::Srgb<u8>`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl Color {
+    pub const fn new(red: u8, green: u8, blue: u8) -> Self {
+        Self { red, green, blue }
+    }
+
+    // Components as sRGB values in 0..1
+    pub fn to_f32(self) -> [f32; 3] {
+        [self.red as f32 / 255.0, self.green as f32 / 255.0, self.blue as f32 / 255.0]
+    }
+
+    // Nearest 8-bit color to sRGB values in 0..1
+    pub fn from_f32([red, green, blue]: [f32; 3]) -> Self {
+        let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        Self::new(channel(red), channel(green), channel(blue))
+    }
+}
+
+// Color space two colors are blended in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Interpolation {
+    #[default]
+    Srgb,                                   // Straight on the stored values (can look muddy in between)
+    LinearRgb,                              // On physical light intensity, brighter midpoints
+    Oklab,                                  // Perceptually even steps in lightness and hue
+}
+
+impl Interpolation {
+    pub const ALL: [Interpolation; 3] = [Interpolation::Srgb, Interpolation::LinearRgb, Interpolation::Oklab];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Interpolation::Srgb => "SRGB",
+            Interpolation::LinearRgb => "LINEAR",
+            Interpolation::Oklab => "OKLAB",
+        }
+    }
+
+    // The space after this one, wrapping around
+    pub fn next(self) -> Interpolation {
+        let index = Interpolation::ALL.iter().position(|&i| i == self).unwrap_or(0);
+        Interpolation::ALL[(index + 1) % Interpolation::ALL.len()]
+    }
+}
+
+// Blend two sRGB colors (components in 0..1) in the given space
+pub fn lerp(start: [f32; 3], end: [f32; 3], percent: f32, interpolation: Interpolation) -> [f32; 3] {
+    let mix = |a: [f32; 3], b: [f32; 3]| [0, 1, 2].map(|i| a[i] + percent * (b[i] - a[i]));
+    match interpolation {
+        Interpolation::Srgb => mix(start, end),
+        Interpolation::LinearRgb => linear_to_srgb(mix(srgb_to_linear(start), srgb_to_linear(end))),
+        Interpolation::Oklab => {
+            linear_to_srgb(oklab_to_linear(mix(linear_to_oklab(srgb_to_linear(start)), linear_to_oklab(srgb_to_linear(end)))))
+        }
+    }
+}
+
+// Sample evenly spaced color stops at `t` (0 at the first stop, 1 at the last)
+pub fn gradient(stops: &[Color], t: f32, interpolation: Interpolation) -> [f32; 3] {
+    match stops {
+        [] => [1.0; 3],
+        [only] => only.to_f32(),
+        _ => {
+            let position = t.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+            let index = (position.floor() as usize).min(stops.len() - 2);
+            lerp(stops[index].to_f32(), stops[index + 1].to_f32(), position - index as f32, interpolation)
+        }
+    }
+}
+
+// HSL (hue in degrees, saturation and lightness in 0..1) to sRGB
+pub fn hsl_to_srgb(hue: f32, saturation: f32, lightness: f32) -> [f32; 3] {
+    let (saturation, lightness) = (saturation.clamp(0.0, 1.0), lightness.clamp(0.0, 1.0));
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let [r, g, b] = match sector as u32 {
+        0 => [chroma, x, 0.0],
+        1 => [x, chroma, 0.0],
+        2 => [0.0, chroma, x],
+        3 => [0.0, x, chroma],
+        4 => [x, 0.0, chroma],
+        _ => [chroma, 0.0, x],
+    };
+    let m = lightness - chroma / 2.0;
+    [r + m, g + m, b + m]
+}
+
+// OKLCh (lightness in 0..1, chroma, hue in degrees) to sRGB. Colors outside
+// the sRGB gamut are clipped.
+pub fn oklch_to_srgb(lightness: f32, chroma: f32, hue: f32) -> [f32; 3] {
+    let (sin, cos) = hue.to_radians().sin_cos();
+    linear_to_srgb(oklab_to_linear([lightness, chroma * cos, chroma * sin])).map(|c| c.clamp(0.0, 1.0))
+}
+
+// sRGB transfer curve, decoding to linear light
+pub fn srgb_to_linear(color: [f32; 3]) -> [f32; 3] {
+    color.map(|c| if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) })
+}
+
+// sRGB transfer curve, encoding linear light
+pub fn linear_to_srgb(color: [f32; 3]) -> [f32; 3] {
+    color.map(|c| {
+        let c = c.max(0.0);
+        if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 }
+    })
+}
+
+// Linear RGB to OKLab (https://bottosson.github.io/posts/oklab/), in f64
+// so the published coefficients keep their precision
+fn linear_to_oklab(color: [f32; 3]) -> [f32; 3] {
+    let [r, g, b] = color.map(f64::from);
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+    [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    ].map(|c| c as f32)
+}
+
+fn oklab_to_linear(color: [f32; 3]) -> [f32; 3] {
+    let [lightness, a, b] = color.map(f64::from);
+    let l = (lightness + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+    let m = (lightness - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+    let s = (lightness - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+    [
+        4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+        -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+        -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+    ].map(|c| c as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLACK: [f32; 3] = [0.0; 3];
+    const WHITE: [f32; 3] = [1.0; 3];
+
+    fn close(a: [f32; 3], b: [f32; 3]) -> bool {
+        (0..3).all(|i| (a[i] - b[i]).abs() < 1e-3)
+    }
+
+    #[test]
+    fn midpoints_depend_on_the_space() {
+        // Halfway in OKLab lightness is 0.125 linear light, about 0.389 encoded
+        assert!(close(lerp(BLACK, WHITE, 0.5, Interpolation::Oklab), [0.3885; 3]));
+        assert!(close(lerp(BLACK, WHITE, 0.5, Interpolation::LinearRgb), [0.7354; 3]));
+        assert_eq!(lerp(BLACK, WHITE, 0.5, Interpolation::Srgb), [0.5; 3]);
+    }
+
+    #[test]
+    fn blends_start_and_end_on_their_colors() {
+        let (orange, teal) = ([1.0, 0.53, 0.0], [0.0, 0.5, 0.5]);
+        for interpolation in Interpolation::ALL {
+            assert!(close(lerp(orange, teal, 0.0, interpolation), orange), "{}", interpolation.name());
+            assert!(close(lerp(orange, teal, 1.0, interpolation), teal), "{}", interpolation.name());
+        }
+    }
+
+    #[test]
+    fn oklab_keeps_grays_gray() {
+        let [lightness, a, b] = linear_to_oklab(srgb_to_linear(WHITE));
+        assert!((lightness - 1.0).abs() < 1e-3 && a.abs() < 1e-3 && b.abs() < 1e-3);
+        let gray = lerp([0.2; 3], [0.9; 3], 0.3, Interpolation::Oklab);
+        assert!((gray[0] - gray[1]).abs() < 1e-4 && (gray[1] - gray[2]).abs() < 1e-4);
+    }
+
+    #[test]
+    fn gradients_pass_through_every_stop() {
+        let stops = [Color::new(255, 0, 0), Color::new(0, 255, 0), Color::new(0, 0, 255)];
+        assert!(close(gradient(&stops, 0.0, Interpolation::Oklab), stops[0].to_f32()));
+        assert!(close(gradient(&stops, 0.5, Interpolation::Oklab), stops[1].to_f32()));
+        assert!(close(gradient(&stops, 1.5, Interpolation::Oklab), stops[2].to_f32()));
+        assert_eq!(gradient(&stops[..1], 0.7, Interpolation::Srgb), stops[0].to_f32());
+    }
+}
@@ -0,0 +1,128 @@
+use std::f32::consts::TAU;
+
+// Spring easing settings
+const SPRING_DAMPING: f32 = 6.0;            // How quickly the overshoot settles
+const SPRING_OSCILLATIONS: f32 = 1.5;       // Number of wobbles over the lifetime
+
+// Maps linear progress (0 at the start, 1 at the end) onto a curve
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Easing {
+    #[default]
+    Linear,
+    EaseOutCubic,                           // Fast start, gentle stop
+    Exponential,                            // Very fast start, long tail
+    Spring,                                 // Overshoots and settles like a damped spring
+    CubicBezier(f32, f32, f32, f32),        // CSS-style curve through (x1, y1) and (x2, y2)
+}
+
+impl Easing {
+    // Curves offered in the menu, in cycling order
+    pub const PRESETS: [Easing; 5] = [
+        Easing::Linear,
+        Easing::EaseOutCubic,
+        Easing::Exponential,
+        Easing::Spring,
+        Easing::CubicBezier(0.2, 0.9, 0.3, 1.0),
+    ];
+
+    // Evaluate the curve at progress `t` (clamped to 0..=1)
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseOutCubic => 1.0 - (1.0 - t).powi(3),
+            Easing::Exponential => {
+                if t >= 1.0 { 1.0 } else { 1.0 - 2.0_f32.powf(-10.0 * t) }
+            }
+            Easing::Spring => {
+                1.0 - (-SPRING_DAMPING * t).exp() * (TAU * SPRING_OSCILLATIONS * t).cos()
+            }
+            Easing::CubicBezier(x1, y1, x2, y2) => cubic_bezier(x1, y1, x2, y2, t),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Easing::Linear => "LINEAR",
+            Easing::EaseOutCubic => "CUBIC",
+            Easing::Exponential => "EXPO",
+            Easing::Spring => "SPRING",
+            Easing::CubicBezier(..) => "BEZIER",
+        }
+    }
+
+    // The preset after this one, wrapping around. Any custom Bezier curve
+    // counts as the Bezier preset.
+    pub fn next(self) -> Easing {
+        let index = Easing::PRESETS.iter()
+            .position(|preset| preset.name() == self.name())
+            .unwrap_or(0);
+        Easing::PRESETS[(index + 1) % Easing::PRESETS.len()]
+    }
+}
+
+// One coordinate of a cubic Bezier from (0, 0) to (1, 1) with the given control values
+fn bezier_coordinate(a: f32, b: f32, s: f32) -> f32 {
+    let inv = 1.0 - s;
+    3.0 * inv * inv * s * a + 3.0 * inv * s * s * b + s * s * s
+}
+
+// Solve x(s) = x for the curve parameter, then return y(s)
+fn cubic_bezier(x1: f32, y1: f32, x2: f32, y2: f32, x: f32) -> f32 {
+    // Bisection is robust for any control points with x1, x2 in 0..=1
+    let (x1, x2) = (x1.clamp(0.0, 1.0), x2.clamp(0.0, 1.0));
+    let (mut low, mut high) = (0.0, 1.0);
+    let mut s = x;
+    for _ in 0..24 {
+        let value = bezier_coordinate(x1, x2, s);
+        if (value - x).abs() < 1e-5 {
+            break;
+        }
+        if value < x { low = s; } else { high = s; }
+        s = (low + high) / 2.0;
+    }
+    bezier_coordinate(y1, y2, s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_curve_starts_at_zero_and_ends_at_one() {
+        for easing in Easing::PRESETS {
+            assert_eq!(easing.apply(0.0), 0.0, "{}", easing.name());
+            assert!((easing.apply(1.0) - 1.0).abs() < 0.01, "{}", easing.name());
+            assert_eq!(easing.apply(-1.0), easing.apply(0.0), "{}", easing.name());
+            assert_eq!(easing.apply(2.0), easing.apply(1.0), "{}", easing.name());
+        }
+    }
+
+    #[test]
+    fn springs_overshoot_before_settling() {
+        assert!((0..100).map(|i| Easing::Spring.apply(i as f32 / 100.0)).any(|value| value > 1.0));
+    }
+
+    #[test]
+    fn bezier_curves_solve_for_x() {
+        // CSS `ease`, whose value halfway along is known
+        let ease = Easing::CubicBezier(0.25, 0.1, 0.25, 1.0);
+        assert!((ease.apply(0.5) - 0.8024).abs() < 1e-3);
+        // Control points on the diagonal give a straight line
+        let straight = Easing::CubicBezier(1.0 / 3.0, 1.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0);
+        for i in 0..=10 {
+            let t = i as f32 / 10.0;
+            assert!((straight.apply(t) - t).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn next_cycles_through_the_presets() {
+        let mut easing = Easing::Linear;
+        for preset in Easing::PRESETS.iter().cycle().skip(1).take(Easing::PRESETS.len()) {
+            easing = easing.next();
+            assert_eq!(easing, *preset);
+        }
+        assert_eq!(Easing::CubicBezier(0.1, 0.2, 0.3, 0.4).next(), Easing::Linear);
+    }
+}
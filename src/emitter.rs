@@ -0,0 +1,282 @@
+use crate::geom::Point;
+use crate::ripple::{RippleKind, RippleParams};
+
+// Minimum time between ripples spawned while the mouse is held down
+pub const SPAWN_INTERVAL: f32 = 0.1;
+
+// Upper bound on ripples spawned in one frame by distance spacing
+pub const MAX_SPAWNS_PER_FRAME: usize = 32;
+
+// How a held button decides when to spawn the next ripple
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Throttle {
+    Time { interval: f32 },                 // One ripple every `interval` seconds, even when still
+    Distance { spacing: f32 },              // One ripple every `spacing` pixels along the cursor path, none when still
+}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Throttle::Time { interval: SPAWN_INTERVAL }
+    }
+}
+
+// Drag velocity response
+pub const REFERENCE_SPEED: f32 = 700.0;     // Cursor speed (pixels per second) treated as a fast flick
+pub const MIN_STRENGTH: f32 = 0.5;          // Strength of ripples from a still or slow cursor
+pub const MAX_STRENGTH: f32 = 2.2;          // Strength of ripples from a very fast flick
+pub const MAX_STRETCH: f32 = 1.8;           // Elongation along the motion at full speed
+pub const VELOCITY_SMOOTHING: f32 = 0.05;   // Time constant of the velocity filter, in seconds
+pub const MAX_RINGS: i16 = 12;              // Upper bound on rings after scaling
+
+// A request to create one ripple, as produced by an input source
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Spawn {
+    pub position: Point,                    // Where the ripple starts
+    pub kind: RippleKind,                   // Preset the ripple is based on
+    pub strength: f32,                      // Scales size, ring count and stroke weight (1 = unchanged)
+    pub stretch: f32,                       // Elongation along `direction` (1 = circle)
+    pub direction: f32,                     // Direction of motion in radians
+}
+
+impl Spawn {
+    // A plain ripple of the given kind
+    pub fn new(position: Point, kind: RippleKind) -> Self {
+        Self { position, kind, strength: 1.0, stretch: 1.0, direction: 0.0 }
+    }
+
+    // Apply this spawn's strength and stretch to the kind's parameters
+    pub fn params(&self, base: RippleParams) -> RippleParams {
+        RippleParams {
+            amount: ((base.amount as f32 * self.strength).round() as i16).clamp(1, MAX_RINGS),
+            max_radius: base.max_radius * self.strength,
+            fade_distance: base.fade_distance * self.strength,
+            stroke_weight: base.stroke_weight * self.strength.sqrt(),
+            stretch: base.stretch * self.stretch,
+            direction: self.direction,
+            ..base
+        }
+    }
+}
+
+// Turns a held mouse button into a stream of spawns. Tracks the cursor
+// velocity so fast flicks make big splashes and slow drags small ripples.
+#[derive(Clone, Debug)]
+pub struct Emitter {
+    pub velocity_scaling: bool,             // Scale ripples by cursor speed
+    pub stretch: bool,                      // Stretch ripples into ellipses along the motion
+    pub throttle: Throttle,                 // Time or distance based spacing of dragged ripples
+    kind: RippleKind,                       // Kind spawned by the current press
+    held: bool,                             // Whether a button is currently held
+    last_position: Option<Point>,           // Cursor position at the previous update
+    velocity: Point,                        // Smoothed cursor velocity in pixels per second
+    last_spawn_time: f32,                   // Time when last ripple was created
+    travelled: f32,                         // Path length covered since the last ripple
+}
+
+impl Default for Emitter {
+    fn default() -> Self {
+        Self {
+            velocity_scaling: true,
+            stretch: false,
+            throttle: Throttle::default(),
+            kind: RippleKind::Drop,
+            held: false,
+            last_position: None,
+            velocity: Point::default(),
+            last_spawn_time: 0.0,
+            travelled: 0.0,
+        }
+    }
+}
+
+impl Emitter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_held(&self) -> bool {
+        self.held
+    }
+
+    // Smoothed cursor velocity in pixels per second
+    pub fn velocity(&self) -> Point {
+        self.velocity
+    }
+
+    // Start a press; the first ripple always appears at full default strength
+    pub fn press(&mut self, position: Point, kind: RippleKind, time: f32) -> Spawn {
+        self.held = true;
+        self.kind = kind;
+        self.last_position = Some(position);
+        self.velocity = Point::default();
+        self.last_spawn_time = time;
+        self.travelled = 0.0;
+        Spawn::new(position, kind)
+    }
+
+    pub fn release(&mut self) {
+        self.held = false;
+        self.last_position = None;
+    }
+
+    // Follow the cursor while held. Returns the ripples to spawn this frame.
+    pub fn drag(&mut self, position: Point, time: f32, dt: f32) -> Vec<Spawn> {
+        if !self.held {
+            return Vec::new();
+        }
+        let previous = self.last_position;
+        self.track(position, dt);
+
+        match self.throttle {
+            // Create ripples with some spacing in time
+            Throttle::Time { interval } => {
+                if time - self.last_spawn_time > interval {
+                    self.last_spawn_time = time;
+                    return vec![self.spawn_at(position)];
+                }
+                Vec::new()
+            }
+            // Place ripples at even steps along the path since last frame
+            Throttle::Distance { spacing } => match previous {
+                Some(previous) => self.spawn_along(previous, position, spacing.max(1.0)),
+                None => Vec::new(),
+            },
+        }
+    }
+
+    // Spawn every `spacing` pixels along the segment from `from` to `to`,
+    // carrying the leftover distance over to the next frame
+    fn spawn_along(&mut self, from: Point, to: Point, spacing: f32) -> Vec<Spawn> {
+        let length = from.distance(to);
+        let mut spawns = Vec::new();
+        // A still cursor covers no distance and places nothing
+        if length <= 0.0 {
+            return spawns;
+        }
+        // Never behind the start, e.g. after the spacing was made smaller
+        let mut offset = (spacing - self.travelled).max(0.0);
+        while offset <= length && spawns.len() < MAX_SPAWNS_PER_FRAME {
+            let t = offset / length;
+            let position = Point::new(from.x + (to.x - from.x) * t, from.y + (to.y - from.y) * t);
+            spawns.push(self.spawn_at(position));
+            offset += spacing;
+        }
+
+        if spawns.is_empty() {
+            self.travelled += length;
+        } else {
+            // Distance from the last ripple placed to the end of the segment.
+            // When the per-frame cap cut the loop short that is more than the
+            // spacing; the skipped stretch is dropped so the next ripple lands
+            // on the next segment instead of behind it.
+            self.travelled = (length - (offset - spacing)).clamp(0.0, spacing);
+        }
+        spawns
+    }
+
+    // Update the smoothed velocity from the cursor movement since last frame
+    fn track(&mut self, position: Point, dt: f32) {
+        if let Some(last) = self.last_position && dt > 0.0 {
+            let instant = Point::new((position.x - last.x) / dt, (position.y - last.y) / dt);
+            let blend = 1.0 - (-dt / VELOCITY_SMOOTHING).exp();
+            self.velocity = Point::new(
+                self.velocity.x + (instant.x - self.velocity.x) * blend,
+                self.velocity.y + (instant.y - self.velocity.y) * blend,
+            );
+        }
+        self.last_position = Some(position);
+    }
+
+    // Build a spawn shaped by the current cursor velocity
+    fn spawn_at(&self, position: Point) -> Spawn {
+        let mut spawn = Spawn::new(position, self.kind);
+        let speed = self.velocity.length();
+        // Saturating response: 0 when still, approaching 1 for fast flicks
+        let response = 1.0 - (-speed / REFERENCE_SPEED).exp();
+
+        if self.velocity_scaling {
+            spawn.strength = MIN_STRENGTH + (MAX_STRENGTH - MIN_STRENGTH) * response;
+        }
+        if self.stretch && speed > 0.0 {
+            spawn.stretch = 1.0 + (MAX_STRETCH - 1.0) * response;
+            spawn.direction = self.velocity.y.atan2(self.velocity.x);
+        }
+        spawn
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn emitter(throttle: Throttle) -> Emitter {
+        Emitter { throttle, velocity_scaling: false, ..Emitter::new() }
+    }
+
+    #[test]
+    fn time_throttling_spawns_once_per_interval() {
+        let mut emitter = emitter(Throttle::Time { interval: 0.1 });
+        emitter.press(Point::default(), RippleKind::Drop, 0.0);
+        let counts: Vec<usize> = (1..=10)
+            .map(|frame| emitter.drag(Point::default(), frame as f32 * 0.05, 0.05).len())
+            .collect();
+        assert_eq!(counts, [0, 0, 1, 0, 0, 1, 0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn distance_throttling_spaces_ripples_along_the_path() {
+        let mut emitter = emitter(Throttle::Distance { spacing: 10.0 });
+        emitter.press(Point::default(), RippleKind::Drop, 0.0);
+        let spawns = emitter.drag(Point::new(25.0, 0.0), 0.1, 0.1);
+        let xs: Vec<f32> = spawns.iter().map(|spawn| spawn.position.x).collect();
+        assert_eq!(xs, [10.0, 20.0]);
+        // The 5 pixels left over count towards the next ripple
+        let spawns = emitter.drag(Point::new(30.0, 0.0), 0.2, 0.1);
+        assert_eq!(spawns.len(), 1);
+        assert_eq!(spawns[0].position.x, 30.0);
+    }
+
+    #[test]
+    fn distance_throttling_places_nothing_while_still() {
+        let mut emitter = emitter(Throttle::Distance { spacing: 10.0 });
+        emitter.press(Point::default(), RippleKind::Drop, 0.0);
+        for frame in 1..20 {
+            assert!(emitter.drag(Point::default(), frame as f32 * 0.1, 0.1).is_empty());
+        }
+    }
+
+    #[test]
+    fn distance_throttling_restarts_after_a_smaller_spacing() {
+        let mut emitter = emitter(Throttle::Distance { spacing: 100.0 });
+        emitter.press(Point::default(), RippleKind::Drop, 0.0);
+        assert!(emitter.drag(Point::new(90.0, 0.0), 0.1, 0.1).is_empty());
+        // 90 pixels travelled is already past the new spacing: the next
+        // ripple lands at the start of the segment, never behind it
+        emitter.throttle = Throttle::Distance { spacing: 10.0 };
+        let spawns = emitter.drag(Point::new(95.0, 0.0), 0.2, 0.1);
+        assert_eq!(spawns.len(), 1);
+        assert_eq!(spawns[0].position.x, 90.0);
+    }
+
+    #[test]
+    fn distance_throttling_caps_spawns_per_frame() {
+        let mut emitter = emitter(Throttle::Distance { spacing: 1.0 });
+        emitter.press(Point::default(), RippleKind::Drop, 0.0);
+        assert_eq!(emitter.drag(Point::new(1000.0, 0.0), 0.1, 0.1).len(), MAX_SPAWNS_PER_FRAME);
+        // The skipped stretch is dropped: the next ripple starts the next segment
+        let spawns = emitter.drag(Point::new(1000.5, 0.0), 0.2, 0.1);
+        assert_eq!(spawns.len(), 1);
+        assert_eq!(spawns[0].position.x, 1000.0);
+    }
+
+    #[test]
+    fn fast_drags_make_stronger_stretched_ripples() {
+        let mut emitter = Emitter { stretch: true, ..Emitter::new() };
+        emitter.press(Point::default(), RippleKind::Drop, 0.0);
+        let slow = emitter.drag(Point::new(10.0, 0.0), 0.15, 0.15)[0];
+        let fast = emitter.drag(Point::new(210.0, 0.0), 0.3, 0.02)[0];
+        assert!(MIN_STRENGTH <= slow.strength && slow.strength < fast.strength && fast.strength <= MAX_STRENGTH);
+        assert!(1.0 < slow.stretch && slow.stretch < fast.stretch && fast.stretch <= MAX_STRETCH);
+        assert_eq!(fast.direction, 0.0, "stretched along the motion");
+    }
+}
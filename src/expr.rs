@@ -0,0 +1,245 @@
+// Small arithmetic expressions for timeline positions, e.g. "w/4 * cos(n * pi / 4)".
+// Numbers, named variables, + - * / ^ (power), unary minus, parentheses and
+// the functions sin, cos, tan, abs, sqrt, floor, min and max are supported.
+
+// A parsed expression, ready to be evaluated many times
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Number(f32),
+    Variable(String),
+    Negate(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+impl Expr {
+    pub fn parse(text: &str) -> Result<Expr, String> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser { tokens, position: 0 };
+        let expr = parser.sum()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(token) => Err(format!("unexpected '{}' in '{}'", token, text)),
+        }
+    }
+
+    // Value of the expression, looking variables up in `variables` ("pi" is always known)
+    pub fn eval(&self, variables: &[(&str, f32)]) -> Result<f32, String> {
+        Ok(match self {
+            Expr::Number(value) => *value,
+            Expr::Variable(name) if name == "pi" => std::f32::consts::PI,
+            Expr::Variable(name) => variables
+                .iter()
+                .find(|(variable, _)| variable == name)
+                .map(|&(_, value)| value)
+                .ok_or_else(|| format!("unknown variable '{}'", name))?,
+            Expr::Negate(inner) => -inner.eval(variables)?,
+            Expr::Binary(op, left, right) => {
+                let (left, right) = (left.eval(variables)?, right.eval(variables)?);
+                match op {
+                    '+' => left + right,
+                    '-' => left - right,
+                    '*' => left * right,
+                    '/' => left / right,
+                    _ => left.powf(right),
+                }
+            }
+            Expr::Call(name, args) => {
+                let args = args.iter().map(|arg| arg.eval(variables)).collect::<Result<Vec<_>, _>>()?;
+                match (name.as_str(), args.as_slice()) {
+                    ("sin", &[x]) => x.sin(),
+                    ("cos", &[x]) => x.cos(),
+                    ("tan", &[x]) => x.tan(),
+                    ("abs", &[x]) => x.abs(),
+                    ("sqrt", &[x]) => x.sqrt(),
+                    ("floor", &[x]) => x.floor(),
+                    ("min", &[a, b]) => a.min(b),
+                    ("max", &[a, b]) => a.max(b),
+                    _ => return Err(format!("unknown function '{}' with {} arguments", name, args.len())),
+                }
+            }
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f32),
+    Name(String),
+    Symbol(char),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Token::Number(value) => write!(f, "{}", value),
+            Token::Name(name) => write!(f, "{}", name),
+            Token::Symbol(symbol) => write!(f, "{}", symbol),
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut number = String::new();
+            while let Some(&c) = chars.peek() && (c.is_ascii_digit() || c == '.') {
+                number.push(c);
+                chars.next();
+            }
+            let value = number.parse().map_err(|_| format!("'{}' is not a number", number))?;
+            tokens.push(Token::Number(value));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut name = String::new();
+            while let Some(&c) = chars.peek() && (c.is_alphanumeric() || c == '_') {
+                name.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Name(name));
+        } else if "+-*/^(),".contains(c) {
+            tokens.push(Token::Symbol(c));
+            chars.next();
+        } else {
+            return Err(format!("unexpected '{}' in '{}'", c, text));
+        }
+    }
+    Ok(tokens)
+}
+
+// Recursive descent over the usual precedence levels:
+// sum (+ -) < product (* /) < unary minus < power (^, right associative) < atom
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        if self.peek() == Some(&Token::Symbol(symbol)) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut expr = self.product()?;
+        while let Some(&Token::Symbol(op @ ('+' | '-'))) = self.peek() {
+            self.position += 1;
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.product()?));
+        }
+        Ok(expr)
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        while let Some(&Token::Symbol(op @ ('*' | '/'))) = self.peek() {
+            self.position += 1;
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat('-') {
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<Expr, String> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            return Ok(Expr::Binary('^', Box::new(base), Box::new(self.unary()?)));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(Expr::Number(value)),
+            Some(Token::Name(name)) => {
+                if !self.eat('(') {
+                    return Ok(Expr::Variable(name));
+                }
+                let mut args = Vec::new();
+                if !self.eat(')') {
+                    loop {
+                        args.push(self.sum()?);
+                        if self.eat(')') {
+                            break;
+                        }
+                        if !self.eat(',') {
+                            return Err(format!("expected ',' or ')' in call to '{}'", name));
+                        }
+                    }
+                }
+                Ok(Expr::Call(name, args))
+            }
+            Some(Token::Symbol('(')) => {
+                let expr = self.sum()?;
+                if !self.eat(')') {
+                    return Err("missing ')'".to_string());
+                }
+                Ok(expr)
+            }
+            Some(token) => Err(format!("unexpected '{}'", token)),
+            None => Err("expression ends too early".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(text: &str) -> f32 {
+        Expr::parse(text).unwrap().eval(&[("w", 800.0), ("n", 2.0)]).unwrap()
+    }
+
+    #[test]
+    fn follows_the_usual_precedence() {
+        assert_eq!(eval("1 + 2 * 3"), 7.0);
+        assert_eq!(eval("(1 + 2) * 3"), 9.0);
+        assert_eq!(eval("10 - 4 - 3"), 3.0);
+        assert_eq!(eval("12 / 2 / 3"), 2.0);
+        assert_eq!(eval("2 ^ 3 ^ 2"), 512.0);
+        assert_eq!(eval("-2 ^ 2"), -4.0);
+        assert_eq!(eval("2 ^ -1"), 0.5);
+    }
+
+    #[test]
+    fn looks_up_variables_and_calls_functions() {
+        assert_eq!(eval("w / 4"), 200.0);
+        assert_eq!(eval("max(n, 3) + min(n, 3)"), 5.0);
+        assert_eq!(eval("floor(2.7) + abs(-1) + sqrt(16)"), 7.0);
+        assert!((eval("w/4 * cos(n * pi / 4)")).abs() < 1e-3);
+    }
+
+    #[test]
+    fn reports_bad_input() {
+        assert!(Expr::parse("1 +").is_err());
+        assert!(Expr::parse("(1 + 2").is_err());
+        assert!(Expr::parse("1 2").is_err());
+        assert!(Expr::parse("3 $ 4").is_err());
+        assert!(Expr::parse("max(1 2)").is_err());
+        assert!(Expr::parse("1..2").is_err());
+        let unknown = Expr::parse("h / 2").unwrap().eval(&[("w", 1.0)]);
+        assert_eq!(unknown, Err("unknown variable 'h'".to_string()));
+        assert!(Expr::parse("min(1)").unwrap().eval(&[]).is_err());
+    }
+}
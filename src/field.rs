@@ -0,0 +1,254 @@
+use crate::color::Color;
+use crate::emitter::Spawn;
+use crate::geom::Point;
+use crate::obstacle::{self, Obstacle};
+use crate::palette::{ColorPicker, PaletteRegistry};
+use crate::random::{self, SimRng};
+use crate::reflection::Mirror;
+use crate::ripple::{Ripple, RippleKind, RippleParams};
+use crate::shape::RippleShape;
+use crate::splash::Splashes;
+
+// Step used by `Timing::PerFrame`, matching the original 60 fps tuning
+pub const LEGACY_FRAME_DELTA: f32 = 1.0 / 60.0;
+
+// Longest step simulated in one update, so a stalled window does not
+// make every ripple jump straight to the end of its life
+pub const MAX_FRAME_DELTA: f32 = 0.25;
+
+// How elapsed time is fed into the simulation
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Timing {
+    #[default]
+    RealTime,                               // Advance by the measured frame delta
+    PerFrame,                               // Advance a fixed step per frame (old refresh-rate dependent behaviour)
+}
+
+impl Timing {
+    // Convert a measured frame delta into the step the simulation should take
+    pub fn step(self, dt: f32) -> f32 {
+        match self {
+            Timing::RealTime => dt.clamp(0.0, MAX_FRAME_DELTA),
+            Timing::PerFrame => LEGACY_FRAME_DELTA,
+        }
+    }
+}
+
+// Owns every live ripple plus the growth and expiry rules. Front ends feed
+// it spawns (usually from an `Emitter`) and read `ripples` back to draw.
+#[derive(Clone, Debug)]
+pub struct RippleField {
+    pub ripples: Vec<Ripple>,               // List of active ripples
+    pub timing: Timing,                     // Real delta time or legacy per-frame steps
+    pub obstacles: Vec<Obstacle>,           // Rocks and walls that block ripples
+    pub kinds: [RippleParams; 3],           // Parameters for each `RippleKind`, indexed by kind
+    pub shape: RippleShape,                 // Outline given to newly spawned ripples
+    pub palettes: PaletteRegistry,          // Available palettes; ring colors come from the active one
+    pub picker: ColorPicker,                // How ring colors are drawn from the palette
+    pub splashes: Splashes,                 // Droplets thrown by new ripples (off by default)
+    seed: u64,                              // Seed the random generator started from
+    spawned: u64,                           // Ripples spawned so far, numbers the next one
+    rng: SimRng,                            // Ring colors and splashes; only spawns draw from it
+}
+
+impl Default for RippleField {
+    fn default() -> Self {
+        Self {
+            ripples: Vec::new(),
+            timing: Timing::default(),
+            obstacles: Vec::new(),
+            kinds: RippleKind::ALL.map(RippleKind::default_params),
+            shape: RippleShape::default(),
+            palettes: PaletteRegistry::new(),
+            picker: ColorPicker::default(),
+            splashes: Splashes::new(),
+            seed: 0,
+            spawned: 0,
+            rng: random::seeded(0),
+        }
+    }
+}
+
+impl RippleField {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Parameters currently used for ripples of the given kind
+    pub fn params(&self, kind: RippleKind) -> RippleParams {
+        self.kinds[kind as usize]
+    }
+
+    pub fn params_mut(&mut self, kind: RippleKind) -> &mut RippleParams {
+        &mut self.kinds[kind as usize]
+    }
+
+    // Spawn a plain ripple of the given kind
+    pub fn spawn(&mut self, position: Point, kind: RippleKind) {
+        self.spawn_with(position, self.params(kind));
+    }
+
+    // Spawn a ripple with explicit parameters (e.g. from a scripted event).
+    // With splashes enabled it also throws a burst of droplets.
+    pub fn spawn_with(&mut self, position: Point, params: RippleParams) {
+        let palette = self.palettes.active();
+        let colors = (0..params.amount.max(1)).map(|_| self.picker.pick(palette, &mut self.rng)).collect();
+        self.push_ripple(position, params, colors);
+        self.splashes.burst(position, &params, &mut self.rng);
+    }
+
+    fn push_ripple(&mut self, position: Point, params: RippleParams, colors: Vec<Color>) {
+        let mut ripple = Ripple::new(position, params, colors, &mut self.rng);
+        ripple.shape = self.shape.clone();
+        ripple.serial = self.spawned;
+        self.spawned += 1;
+        self.ripples.push(ripple);
+    }
+
+    // Spawn the ripple described by an input source
+    pub fn emit(&mut self, spawn: &Spawn) {
+        self.spawn_with(spawn.position, spawn.params(self.params(spawn.kind)));
+    }
+
+    pub fn with_timing(timing: Timing) -> Self {
+        Self { timing, ..Self::default() }
+    }
+
+    // A field whose randomness is fully determined by `seed`
+    pub fn with_seed(seed: u64) -> Self {
+        let mut field = Self::default();
+        field.reseed(seed);
+        field
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // Restart the random sequence (plus sequential color picking and ripple
+    // numbering) from `seed`
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = random::seeded(seed);
+        self.picker.reset();
+        self.spawned = 0;
+    }
+
+    // Advance every ripple and splash droplet by the frame delta `dt` (in
+    // seconds) and drop the ripples that have expired
+    pub fn update(&mut self, dt: f32) {
+        let step = self.timing.step(dt);
+        for ripple in self.ripples.iter_mut() {
+            ripple.update(step);
+        }
+        self.ripples.retain(|ripple| !ripple.is_expired());
+
+        // Landed droplets make small ripples in their own color, without splashing again
+        let secondary = Splashes::secondary_params(self.params(RippleKind::Droplet));
+        for droplet in self.splashes.update(step) {
+            self.push_ripple(droplet.position, secondary, vec![droplet.color; secondary.amount.max(1) as usize]);
+        }
+    }
+
+    // Per-point visibility of a ring outline sampled from `ripple`, as seen
+    // through `mirror`. Everything is fully visible when there are no obstacles.
+    pub fn visibility(&self, ripple: &Ripple, ring: i16, points: &[Point], mirror: &Mirror) -> Vec<f32> {
+        if self.obstacles.is_empty() {
+            return vec![1.0; points.len()];
+        }
+        obstacle::visibility(mirror.apply(ripple.center), ripple.ring_radius(ring), points, &self.obstacles, mirror)
+    }
+
+    pub fn len(&self) -> usize {
+        self.ripples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ripples.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.ripples.clear();
+        self.splashes.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emit_scales_the_kind_by_the_spawn_strength() {
+        let mut field = RippleField::with_seed(1);
+        let spawn = Spawn { strength: 2.0, ..Spawn::new(Point::new(10.0, -5.0), RippleKind::Drop) };
+        field.emit(&spawn);
+        let ripple = &field.ripples[0];
+        assert_eq!(ripple.center, Point::new(10.0, -5.0));
+        assert_eq!(ripple.params.max_radius, 2.0 * RippleKind::Drop.default_params().max_radius);
+        assert_eq!(ripple.color_sequence.len(), ripple.params.amount as usize);
+    }
+
+    #[test]
+    fn update_grows_ripples_and_drops_expired_ones() {
+        let mut field = RippleField::with_seed(1);
+        field.spawn(Point::default(), RippleKind::Drop);
+        field.update(0.2);
+        let ripple = &field.ripples[0];
+        assert_eq!(ripple.age, 0.2);
+        assert!(ripple.radius > ripple.params.initial_radius);
+        assert!(ripple.copies > 1);
+
+        let lifetime = field.ripples[0].params.lifetime();
+        while field.ripples.first().is_some_and(|ripple| ripple.age < lifetime - MAX_FRAME_DELTA) {
+            field.update(MAX_FRAME_DELTA);
+            assert_eq!(field.len(), 1);
+        }
+        field.update(MAX_FRAME_DELTA);
+        assert!(field.is_empty());
+    }
+
+    #[test]
+    fn per_frame_timing_ignores_the_measured_delta() {
+        let mut field = RippleField::with_timing(Timing::PerFrame);
+        field.spawn(Point::default(), RippleKind::Drop);
+        field.update(0.2);
+        assert_eq!(field.ripples[0].age, LEGACY_FRAME_DELTA);
+    }
+
+    #[test]
+    fn real_time_steps_are_capped_after_a_stall() {
+        let mut field = RippleField::new();
+        field.spawn(Point::default(), RippleKind::Drop);
+        field.update(5.0);
+        assert_eq!(field.ripples[0].age, MAX_FRAME_DELTA);
+        field.update(-1.0);
+        assert_eq!(field.ripples[0].age, MAX_FRAME_DELTA, "negative deltas do not rewind");
+    }
+
+    #[test]
+    fn the_same_seed_picks_the_same_colors() {
+        let colors = |seed| {
+            let mut field = RippleField::with_seed(seed);
+            for _ in 0..4 {
+                field.spawn(Point::default(), RippleKind::Swell);
+            }
+            field.ripples.iter().flat_map(|ripple| ripple.color_sequence.clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(colors(7), colors(7));
+        assert_ne!(colors(7), colors(8));
+    }
+
+    #[test]
+    fn reseeding_replays_the_same_ripples() {
+        let mut field = RippleField::with_seed(5);
+        let spawns = [Spawn::new(Point::new(1.0, 2.0), RippleKind::Drop), Spawn::new(Point::new(-3.0, 4.0), RippleKind::Swell)];
+        let run = |field: &mut RippleField| {
+            field.clear();
+            field.reseed(5);
+            spawns.iter().for_each(|spawn| field.emit(spawn));
+            field.ripples.iter().map(|ripple| (ripple.color_sequence.clone(), ripple.noise_offset, ripple.serial)).collect::<Vec<_>>()
+        };
+        let first = run(&mut field);
+        assert_eq!(run(&mut field), first);
+    }
+}
@@ -0,0 +1,58 @@
+// Minimal 2D point type so the simulation does not depend on nannou
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Point {
+    pub const fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    // Distance from the origin (the length of the point as a vector)
+    pub fn length(self) -> f32 {
+        (self.x * self.x + self.y * self.y).sqrt()
+    }
+
+    // Euclidean distance to another point
+    pub fn distance(self, other: Point) -> f32 {
+        ((self.x - other.x).powi(2) + (self.y - other.y).powi(2)).sqrt()
+    }
+}
+
+// Axis-aligned rectangle in the same y-up coordinates as the simulation
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Bounds {
+    pub left: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub top: f32,
+}
+
+impl Bounds {
+    pub const fn new(left: f32, right: f32, bottom: f32, top: f32) -> Self {
+        Self { left, right, bottom, top }
+    }
+
+    // Bounds of a `width` x `height` area centered on the origin
+    pub fn from_size(width: f32, height: f32) -> Self {
+        Self::new(-width / 2.0, width / 2.0, -height / 2.0, height / 2.0)
+    }
+
+    pub fn width(&self) -> f32 {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> f32 {
+        self.top - self.bottom
+    }
+
+    pub fn center(&self) -> Point {
+        Point::new((self.left + self.right) / 2.0, (self.bottom + self.top) / 2.0)
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        point.x >= self.left && point.x <= self.right && point.y >= self.bottom && point.y <= self.top
+    }
+}
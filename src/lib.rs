@@ -0,0 +1,59 @@
+// Headless ripple simulation.
+//
+// Everything needed to spawn, grow and expire ripples lives here with no
+// nannou dependency, so the effect can be embedded in other tools or stepped
+// in tests without a window. The `waves` binary is a nannou front end over it.
+
+pub mod animation;
+pub mod attract;
+pub mod background;
+pub mod color;
+pub mod easing;
+pub mod emitter;
+pub mod expr;
+pub mod field;
+pub mod geom;
+pub mod obstacle;
+pub mod palette;
+pub mod rain;
+pub mod rainbow;
+pub mod random;
+pub mod raster;
+pub mod reflection;
+pub mod renderer;
+pub mod ripple;
+pub mod scene;
+pub mod session;
+pub mod settings;
+pub mod shape;
+pub mod splash;
+pub mod svg;
+pub mod timeline;
+pub mod wave;
+pub mod wobble;
+
+pub use animation::{ApngWriter, GifWriter, Quantization, Quantizer, Seamless};
+pub use attract::{Attract, Pattern};
+pub use background::BackgroundType;
+pub use color::{Color, Interpolation};
+pub use easing::Easing;
+pub use emitter::{Emitter, Spawn, Throttle};
+pub use field::{RippleField, Timing};
+pub use geom::{Bounds, Point};
+pub use obstacle::Obstacle;
+pub use palette::{ColorPicker, Palette, PaletteRegistry, Selection};
+pub use rain::{DropSize, Rain};
+pub use rainbow::{HueModel, Rainbow};
+pub use raster::Canvas;
+pub use reflection::{Edges, Mirror};
+pub use renderer::{Renderer, Script};
+pub use ripple::{Coloring, Ripple, RippleKind, RippleParams};
+pub use scene::{Primitive, RingStyle};
+pub use session::{Action, Recorder, Replay};
+pub use settings::{ColorMode, MenuButton, SceneSettings};
+pub use shape::RippleShape;
+pub use splash::{Particle, Splashes};
+pub use svg::{BackgroundStyle, SvgDocument};
+pub use timeline::Timeline;
+pub use wave::WaveGrid;
+pub use wobble::{Wobble, WobbleMode};
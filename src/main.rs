@@ -1,192 +1,536 @@
 use nannou::prelude::*;
 use noise;
 
+mod cli;
 mod effects;
+mod render;
 mod ui;
 
-use ui::{BackgroundType, Menu};
-
-// Configuration constants
-const AMOUNT: i16 = 5;                  // Maximum number of rings per ripple
-const MAX_RADIUS: f32 = 200.0;          // Maximum radius before ripple disappears
-const RADIUS_INCREMENT: f32 = 1.5;      // How fast ripples grow
-const INITIAL_RADIUS: f32 = 10.0;       // Starting radius for ripples
-const COLOR_CHANGE_FACTOR: i16 = 7;     // Spacing between rings
-const STROKE_WEIGHT: f32 = 3.0;         // Line thickness for ripples
-const FADE_DISTANCE: f32 = 50.0;        // Distance over which ripples fade out
+use cli::{Args, RenderArgs};
+use ui::{Menu, SimulationMode};
+use waves::{background, obstacle, scene, session, timeline, Action, Attract, BackgroundStyle, BackgroundType, palette, random, Emitter, MenuButton, Obstacle, RippleField, Rain, Recorder, Replay, RippleKind, RippleShape, Spawn, SvgDocument, Timeline, Timing, WaveGrid};
 
 // Special effect configuration - now these are controlled from the UI
 const DEFAULT_WOBBLE: bool = true;       // Default wobble effect on rings
-const WOBBLE_AMOUNT: f32 = 0.8;         // How much rings wobble
-const WOBBLE_SPEED: f32 = 2.0;          // Speed of wobble animation
 const DEFAULT_FADE: bool = true;         // Default opacity fade as ripples grow
 
-#[derive(Clone)]
-struct Ripple {
-    center: Point2,                     // Center point of the ripple
-    color_sequence: Vec<rgb::Srgb<u8>>, // Colors for each concentric ring
-    radius: f32,                        // Current radius of the outermost ring
-    copies: i16,                        // Number of concentric rings
+// Use Timing::PerFrame to restore the old refresh-rate dependent speed
+const DEFAULT_TIMING: Timing = Timing::RealTime;
+
+// Height added per second while dragging through the wave simulation
+const WAVE_DRAG_STRENGTH: f32 = 12.0;
+
+// Obstacle placement
+const OBSTACLE_RADIUS: f32 = 30.0;      // Radius of a rock dropped with Shift+click
+const POLYGON_CLOSE_DISTANCE: f32 = 12.0;   // Ctrl+click this close to the first vertex closes a wall
+
+// Seconds a timeline seek jumps with the bracket keys
+const TIMELINE_SEEK_STEP: f32 = 5.0;
+
+// Seconds a background takes to blend into the next one
+const CROSSFADE_DURATION: f32 = 3.0;
+
+// A background being blended out while the menu's current one comes in
+struct Crossfade {
+    from: BackgroundType,
+    progress: f32,                      // 0 (all `from`) to 1 (all new)
 }
 
 struct Model {
-    ripples: Vec<Ripple>,               // List of active ripples
+    field: RippleField,                 // Headless ripple simulation
+    waves: WaveGrid,                    // Height field for the wave simulation mode
     noise: noise::Perlin,               // Noise generator for effects
+    frame_seed: u64,                    // Seeds this frame's background sparkles
+    run_frame: u64,                     // Frames since the run (re)started, numbers the sparkle seeds
+    frame_delta: f32,                   // Seconds covered by the last update, sets the trail fade
     time: f32,                          // Application time
-    mouse_down: bool,                   // Tracks if mouse is currently pressed
-    last_ripple_time: f32,              // Time when last ripple was created
+    emitter: Emitter,                   // Turns mouse presses and drags into ripples
+    rain: Rain,                         // Spawns ripples by itself in rain mode
+    rain_rng: random::SimRng,           // Drop positions and sizes, apart from the ring colors
+    attract: Attract,                   // Scripted patterns shown after a while without input
+    attract_rng: random::SimRng,        // Randomness of the scripted patterns
+    resting_background: Option<BackgroundType>, // Background to restore when attract mode ends
+    crossfade: Option<Crossfade>,       // Background change in progress
+    recorder: Option<Recorder>,         // Session being written with --record
+    replay: Option<Replay>,             // Session being played back with --replay
+    replay_start: Option<(RippleField, Menu)>, // Field and menu as the first pass of the replay began
+    timeline: Option<Timeline>,         // Choreography being played with --timeline
     menu: Menu,                         // UI menu
+    pending_polygon: Vec<waves::Point>, // Vertices of a wall being placed with Ctrl+click
+    shapes: Vec<RippleShape>,           // Shapes the menu cycles through for new ripples
 }
 
-impl Ripple {
-    // Create a new ripple at the specified position
-    fn new(position: Point2, _time: f32) -> Self {
-        let mut colors = Vec::new();
-        colors.push(effects::random_color());
-        
-        Self {
-            center: position,
-            color_sequence: colors,
-            radius: INITIAL_RADIUS,
-            copies: 1,
+fn main() {
+    // `waves render ...` writes frames to files instead of opening a window
+    if std::env::args().nth(1).as_deref() == Some("render") {
+        if let Err(error) = render::run(&RenderArgs::parse()) {
+            eprintln!("Render failed: {}", error);
+            std::process::exit(1);
         }
+        return;
     }
+    nannou::app(model)
+        .event(event)
+        .update(update)
+        .simple_window(view)
+        .run();
+}
 
-    // Update ripple state (growth and spawning new rings)
-    fn update(&mut self, _time: f32) {
-        // Grow the ripple
-        self.radius += RADIUS_INCREMENT;
-        
-        // Add new color rings as the ripple grows
-        if self.copies < AMOUNT && self.radius as i16 > self.copies * COLOR_CHANGE_FACTOR {
-            self.copies += 1;
-            self.color_sequence.push(effects::random_color());
+fn model(app: &App) -> Model {
+    let args = Args::parse();
+    let window_rect = app.window_rect();
+    // A replayed session brings its own seed unless one is given
+    let replay = args.replay.as_ref().and_then(|path| match session::load(path) {
+        Ok(events) => {
+            let mut replay = Replay::new(events);
+            replay.speed = args.replay_speed;
+            replay.looped = args.loop_replay;
+            Some(replay)
         }
-    }
+        Err(error) => {
+            eprintln!("Could not load session: {}", error);
+            None
+        }
+    });
+    let seed = args.seed.or_else(|| replay.as_ref().and_then(Replay::seed)).unwrap_or_else(random::random_seed);
     
-    // Calculate opacity based on ripple age
-    fn opacity(&self, max_radius: f32, fade_enabled: bool) -> f32 {
-        if fade_enabled {
-            // Fade out as the ripple approaches maximum size
-            let fade_start = max_radius - FADE_DISTANCE;
-            if self.radius > fade_start {
-                return 1.0 - (self.radius - fade_start) / FADE_DISTANCE;
+    let menu = Menu::new(
+        window_rect,
+        DEFAULT_WOBBLE,
+        DEFAULT_FADE
+    );  // Initialize the menu with default constants
+    let waves = WaveGrid::new(effects::window_bounds(app), menu.grid_cell_size, menu.damping);
+
+    let mut field = RippleField::with_seed(seed);
+    field.timing = DEFAULT_TIMING;
+
+    let mut model = Model { 
+        field,
+        waves,
+        noise: random::seeded_noise(seed),
+        frame_seed: 0,
+        run_frame: 0,
+        frame_delta: 0.0,
+        time: 0.0,
+        emitter: Emitter::new(),
+        rain: Rain::new(),
+        rain_rng: random::fork(seed, random::RAIN_STREAM),
+        attract: Attract::new(),
+        attract_rng: random::fork(seed, random::ATTRACT_STREAM),
+        resting_background: None,
+        crossfade: None,
+        recorder: None,
+        replay,
+        replay_start: None,
+        timeline: None,
+        menu,
+        pending_polygon: Vec::new(),
+        shapes: RippleShape::PRESETS.to_vec(),
+    };
+
+    if let Some(path) = args.record {
+        match Recorder::create(&path, app.time, seed) {
+            Ok(recorder) => model.recorder = Some(recorder),
+            Err(error) => eprintln!("Could not record session: {}", error),
+        }
+    }
+    if let Some(path) = args.timeline {
+        match timeline::load(&path) {
+            Ok(timeline) => model.timeline = Some(timeline),
+            Err(error) => eprintln!("Could not load timeline: {}", error),
+        }
+    }
+    if let Some(path) = args.obstacles {
+        load_obstacles(&mut model, &path);
+    }
+    // User palettes from the config directory join the built-in ones
+    if let Some(dir) = palette::config_dir() {
+        for error in model.field.palettes.load_dir(&dir) {
+            eprintln!("Could not load palette: {}", error);
+        }
+    }
+    // A custom path joins the menu's shapes and is used right away
+    if let Some(data) = args.shape_path {
+        match RippleShape::from_svg_path(&data) {
+            Ok(shape) => {
+                model.field.shape = shape.clone();
+                model.shapes.push(shape);
             }
+            Err(e) => eprintln!("Could not use shape path: {}", e),
         }
-        return 1.0;
     }
-    
-    // Draw the ripple
-    fn draw(&self, draw: &Draw, _app: &App, time: f32, wobble_enabled: bool, fade_enabled: bool) {
-        let opacity = self.opacity(MAX_RADIUS, fade_enabled);
-        
-        for i in 0..self.copies {
-            let ring_radius = self.radius - i as f32 * COLOR_CHANGE_FACTOR as f32;
-            let color = self.color_sequence[i as usize];
-            
-            // Apply opacity
-            let color_with_alpha = rgba(
-                color.red as f32 / 255.0, 
-                color.green as f32 / 255.0, 
-                color.blue as f32 / 255.0, 
-                opacity
-            );
-            
-            if wobble_enabled {
-                // Create a wobbly effect by drawing points around the circle
-                let points = (0..=360).step_by(5).map(|deg| {
-                    let radian = deg_to_rad(deg as f32);
-                    let wobble = WOBBLE_AMOUNT * (time * WOBBLE_SPEED + deg as f32 / 30.0).sin();
-                    let wobble_radius = ring_radius * (1.0 + wobble * 0.01);
-                    let x = self.center.x + wobble_radius * radian.cos();
-                    let y = self.center.y + wobble_radius * radian.sin();
-                    (pt2(x, y), color_with_alpha)
-                }).collect::<Vec<_>>();
-                
-                draw.polyline()
-                    .weight(STROKE_WEIGHT)
-                    .points_colored(points);
-            } else {
-                // Draw a regular circle
-                draw.ellipse()
-                    .xy(self.center)
-                    .w_h(ring_radius * 2.0, ring_radius * 2.0)
-                    .no_fill()
-                    .stroke(color_with_alpha)
-                    .stroke_weight(STROKE_WEIGHT);
+    model
+}
+
+// Start the run over from its seed: same colors, sparkles and noise as at launch
+fn restart_from_seed(model: &mut Model) {
+    let seed = model.field.seed();
+    model.field.reseed(seed);
+    model.field.clear();
+    model.waves.clear();
+    model.noise = random::seeded_noise(seed);
+    model.rain_rng = random::fork(seed, random::RAIN_STREAM);
+    model.attract_rng = random::fork(seed, random::ATTRACT_STREAM);
+    model.run_frame = 0;
+}
+
+// Append obstacles from a file, reporting problems without stopping the app
+fn load_obstacles(model: &mut Model, path: &std::path::Path) {
+    match obstacle::load(path) {
+        Ok(obstacles) => {
+            for obstacle in obstacles {
+                add_obstacle(model, obstacle);
             }
         }
+        Err(error) => eprintln!("Could not load obstacles: {}", error),
     }
-    
-    // Check if ripple should be removed
-    fn is_expired(&self) -> bool {
-        self.radius >= MAX_RADIUS
+}
+
+// Keep the wave grid and the menu selection in step with the obstacle list
+fn obstacles_changed(model: &mut Model) {
+    model.waves.set_obstacles(&model.field.obstacles);
+    if let Some(selected) = model.menu.selected_obstacle {
+        if selected >= model.field.obstacles.len() {
+            model.menu.selected_obstacle = model.field.obstacles.len().checked_sub(1);
+        }
+    }
+    if model.menu.selected_obstacle.is_none() {
+        model.menu.moving_obstacle = false;
     }
 }
 
-fn main() {
-    nannou::app(model)
-        .event(event)
-        .update(update)
-        .simple_window(view)
-        .run();
+// Place an obstacle, noting it in the session being recorded
+fn add_obstacle(model: &mut Model, obstacle: Obstacle) {
+    record(model, Action::AddObstacle { obstacle: obstacle.to_line() });
+    model.field.obstacles.push(obstacle);
+    obstacles_changed(model);
 }
 
-fn model(app: &App) -> Model {
-    let window_rect = app.window_rect();
+// Move an obstacle's center to `position`, noting it in the session being recorded
+fn move_obstacle(model: &mut Model, index: usize, position: waves::Point) {
+    if let Some(obstacle) = model.field.obstacles.get_mut(index) {
+        obstacle.move_to(position);
+        record(model, Action::MoveObstacle { index, x: position.x, y: position.y });
+        obstacles_changed(model);
+    }
+}
 
-    #[cfg(target_os = "macos")]
-    let noise_gen = noise::Perlin::new(42);
-    #[cfg(not(target_os = "macos"))]
-    let noise_gen = noise::Perlin::new();
-    
-    Model { 
-        ripples: vec![],
-        noise: noise_gen,
-        time: 0.0,
-        mouse_down: false,
-        last_ripple_time: 0.0,
-        menu: Menu::new(
-            window_rect,
-            DEFAULT_WOBBLE,
-            DEFAULT_FADE
-        ),  // Initialize the menu with default constants
+// Remove an obstacle, noting it in the session being recorded
+fn delete_obstacle(model: &mut Model, index: usize) {
+    if index < model.field.obstacles.len() {
+        record(model, Action::DeleteObstacle { index });
+        model.field.obstacles.remove(index);
+        obstacles_changed(model);
     }
 }
 
-fn update(app: &App, model: &mut Model, _update: Update) {
+// Delete the obstacle selected in the menu
+fn delete_selected_obstacle(model: &mut Model) {
+    if let Some(selected) = model.menu.selected_obstacle {
+        delete_obstacle(model, selected);
+    }
+}
+
+// Switch rain and set its rate and wind, noting it in the session being recorded
+fn set_rain(model: &mut Model, enabled: bool, rate: f32, wind: f32) {
+    record(model, Action::Rain { enabled, rate, wind });
+    model.menu.rain_enabled = enabled;
+    model.menu.rain_rate = rate;
+    model.menu.wind = wind;
+}
+
+// Handle a click on one of the menu buttons
+fn press_menu_button(model: &mut Model, button: MenuButton) {
+    // Obstacle buttons only pick what to edit; the edits are recorded themselves
+    if !matches!(button, MenuButton::ObstacleSelect | MenuButton::ObstacleMove | MenuButton::ObstacleDelete) {
+        record(model, Action::Menu { button: button.name().to_string() });
+    }
+    let count = model.field.obstacles.len();
+    match button {
+        // Cycle the selection through the obstacles
+        MenuButton::ObstacleSelect => {
+            model.menu.selected_obstacle = match model.menu.selected_obstacle {
+                _ if count == 0 => None,
+                Some(i) => Some((i + 1) % count),
+                None => Some(0),
+            };
+        }
+        MenuButton::ObstacleMove => {
+            model.menu.moving_obstacle = !model.menu.moving_obstacle && model.menu.selected_obstacle.is_some();
+        }
+        MenuButton::ObstacleDelete => delete_selected_obstacle(model),
+        MenuButton::Seed => restart_from_seed(model),
+        _ => {
+            if !model.menu.scene.press(&mut model.field, &model.shapes, button) {
+                model.menu.press(button);
+            }
+        }
+    }
+}
+
+// Add a vertex to the wall being drawn, closing it when the first vertex is clicked again
+fn add_polygon_vertex(model: &mut Model, position: waves::Point) {
+    let closes = model.pending_polygon.len() >= 3
+        && model.pending_polygon[0].distance(position) < POLYGON_CLOSE_DISTANCE;
+    if closes {
+        close_pending_polygon(model);
+    } else {
+        model.pending_polygon.push(position);
+    }
+}
+
+// Turn the pending vertices into a wall if there are enough of them
+fn close_pending_polygon(model: &mut Model) {
+    let points = std::mem::take(&mut model.pending_polygon);
+    if points.len() >= 3 {
+        add_obstacle(model, Obstacle::Polygon { points });
+    }
+}
+
+fn update(app: &App, model: &mut Model, update: Update) {
     model.time = app.time;
+    let dt = update.since_last.as_secs_f32();
+    model.frame_delta = dt;
     
-    // Update all ripples
-    for ripple in model.ripples.iter_mut() {
-        ripple.update(model.time);
+    // Grow all ripples by the real frame time and remove expired ones
+    model.field.update(dt);
+
+    // Backgrounds draw from a per-frame seed so they stay reproducible
+    // without taking numbers from the ring colors
+    model.frame_seed = random::frame_seed(model.field.seed(), model.run_frame);
+    model.run_frame += 1;
+
+    // Rebuild the wave grid when its resolution or the window size changes
+    let bounds = effects::window_bounds(app);
+    if model.waves.cell_size() != model.menu.grid_cell_size || model.waves.bounds() != bounds {
+        model.waves = WaveGrid::new(bounds, model.menu.grid_cell_size, model.menu.damping);
+        model.waves.set_obstacles(&model.field.obstacles);
+    }
+    model.waves.damping = model.menu.damping;
+    if model.menu.mode == SimulationMode::Waves {
+        model.waves.update(dt);
     }
     
-    // Remove expired ripples
-    model.ripples.retain(|ripple| !ripple.is_expired());
-    
+    // Play back a recorded session. Its rain and attract ripples were
+    // recorded too, so those stay quiet until it is over.
+    let actions = model.replay.as_mut().map(|replay| replay.update(dt)).unwrap_or_default();
+    for action in actions {
+        play(model, action);
+    }
+    let replaying = model.replay.as_ref().is_some_and(|replay| !replay.is_finished());
+
+    // Fire the timeline cues that came due, following the application clock
+    let cues = model.timeline.as_mut().map(|timeline| timeline.update(app.time)).unwrap_or_default();
+    for cue in &cues {
+        play_cue(model, cue, bounds);
+    }
+    let choreographed = model.timeline.as_ref().is_some_and(|timeline| timeline.is_playing() && !timeline.is_finished());
+
+    // Let it rain, alongside whatever the mouse is doing
+    model.rain.enabled = model.menu.rain_enabled && !replaying;
+    model.rain.rate = model.menu.rain_rate;
+    model.rain.size = model.menu.drop_size;
+    model.rain.wind = model.menu.wind;
+    for drop in model.rain.update(dt, bounds, &mut model.rain_rng) {
+        spawn_unattended(model, &drop);
+    }
+
+    // Play scripted patterns once nobody has touched anything for a while,
+    // moving to the next animated background with every pattern. A replay
+    // or a playing timeline counts as input.
+    if !model.menu.attract_enabled || replaying || choreographed {
+        wake(model);
+    }
+    model.attract.enabled = model.menu.attract_enabled;
+    model.attract.timeout = model.menu.idle_timeout;
+    let spawns = model.attract.update(dt, bounds, &mut model.attract_rng);
+    if model.attract.switched() {
+        model.resting_background.get_or_insert(model.menu.scene.background_type);
+        let mut next = model.menu.scene.background_type.next();
+        if next == BackgroundType::None {
+            next = next.next();
+        }
+        switch_background(model, next);
+    }
+    for spawn in spawns {
+        spawn_unattended(model, &spawn);
+    }
+
+    if let Some(crossfade) = &mut model.crossfade {
+        crossfade.progress += dt / CROSSFADE_DURATION;
+        if crossfade.progress >= 1.0 {
+            model.crossfade = None;
+        }
+    }
+
     // Create new ripples while mouse is held down (only if not clicking on UI)
-    if model.mouse_down {
+    model.emitter.velocity_scaling = model.menu.velocity_enabled;
+    model.emitter.stretch = model.menu.stretch_enabled;
+    model.emitter.throttle = model.menu.throttle;
+    if model.emitter.is_held() {
         let current_time = app.time;
         let mouse_pos = app.mouse.position();
         
         // Don't create ripples if mouse is over the menu when it's visible
-        let mouse_over_menu = model.menu.visible && (
-            model.menu.is_in_toggle_button(mouse_pos) ||
-            model.menu.is_in_wobble_button(mouse_pos) ||
-            model.menu.is_in_fade_button(mouse_pos) ||
-            model.menu.is_in_bg_type_button(mouse_pos)
-        );
-        
-        // Create ripples with some spacing in time (every 0.1 seconds)
-        if !mouse_over_menu && current_time - model.last_ripple_time > 0.1 {
-            model.ripples.push(Ripple::new(app.mouse.position(), current_time));
-            model.last_ripple_time = current_time;
+        if !model.menu.contains(mouse_pos, model.field.obstacles.len()) {
+            let position = effects::from_point2(mouse_pos);
+            match model.menu.mode {
+                // Spawn ripples shaped by how fast the cursor is moving
+                SimulationMode::Rings => {
+                    for spawn in model.emitter.drag(position, current_time, dt) {
+                        emit(model, &spawn);
+                    }
+                }
+                // Keep pushing the surface down like a finger dragged through water
+                SimulationMode::Waves => {
+                    impulse(model, position, WAVE_DRAG_STRENGTH * dt);
+                }
+            }
         }
     }
 }
 
+// Drop a ripple that did not come from the mouse (rain or attract mode)
+fn spawn_unattended(model: &mut Model, spawn: &Spawn) {
+    match model.menu.mode {
+        SimulationMode::Rings => emit(model, spawn),
+        SimulationMode::Waves => impulse(model, spawn.position, waves::wave::IMPULSE_STRENGTH * spawn.strength),
+    }
+}
+
+// Spawn a ripple, noting it in the session being recorded
+fn emit(model: &mut Model, spawn: &Spawn) {
+    record(model, Action::spawn(spawn));
+    model.field.emit(spawn);
+}
+
+// Push the wave surface down, noting it in the session being recorded
+fn impulse(model: &mut Model, position: waves::Point, strength: f32) {
+    record(model, Action::Impulse { x: position.x, y: position.y, strength });
+    model.waves.impulse(position, strength);
+}
+
+// Append an action to the session file. Recording stops at the first write error.
+fn record(model: &mut Model, action: Action) {
+    if let Some(recorder) = &mut model.recorder && let Err(error) = recorder.record(model.time, action) {
+        eprintln!("Stopped recording session: {}", error);
+        model.recorder = None;
+    }
+}
+
+// Carry out a timeline cue: spawn its ripple and apply its scene changes
+fn play_cue(model: &mut Model, cue: &timeline::Cue, bounds: waves::Bounds) {
+    let spawned = match model.menu.mode {
+        SimulationMode::Rings => cue.spawn(&mut model.field, bounds),
+        SimulationMode::Waves => cue.position(bounds).map(|position| {
+            if let Some(ripple) = &cue.ripple {
+                impulse(model, position, waves::wave::IMPULSE_STRENGTH * ripple.strength);
+            }
+        }),
+    };
+    if let Err(error) = spawned {
+        eprintln!("Skipping timeline ripple at {}s: {}", cue.time, error);
+    }
+    apply_scene(model, &cue.scene());
+}
+
+// Switch background, wobble and fade as a timeline asks
+fn apply_scene(model: &mut Model, scene: &timeline::SceneState) {
+    if let Some(name) = &scene.background {
+        match BackgroundType::from_name(name) {
+            Some(background) => switch_background(model, background),
+            None => eprintln!("Ignoring unknown background '{}' in timeline", name),
+        }
+    }
+    if let Some(wobble) = scene.wobble {
+        model.menu.scene.wobble_enabled = wobble;
+    }
+    if let Some(fade) = scene.fade {
+        model.menu.scene.fade_enabled = fade;
+    }
+}
+
+// Move the timeline to `time` seconds, starting from a clear field with the
+// scene the skipped cues would have set up
+fn seek_timeline(model: &mut Model, time: f32) {
+    let Some(timeline) = &mut model.timeline else {
+        return;
+    };
+    timeline.seek(time);
+    let scene = timeline.state_at(timeline.clock());
+    model.field.clear();
+    model.waves.clear();
+    apply_scene(model, &scene);
+}
+
+// Carry out an action read from a replayed session
+fn play(model: &mut Model, action: Action) {
+    match action {
+        // Every pass of a looped session starts from a fresh field, and since
+        // menu buttons toggle settings, from the settings the first pass
+        // started with. The session places its own obstacles, so the old
+        // ones go too.
+        Action::Start { .. } => {
+            match &model.replay_start {
+                Some((field, menu)) => {
+                    model.field = field.clone();
+                    model.menu.restore_settings(menu);
+                }
+                None => model.replay_start = Some((model.field.clone(), model.menu.clone())),
+            }
+            restart_from_seed(model);
+            model.field.obstacles.clear();
+            obstacles_changed(model);
+        }
+        Action::Spawn { .. } => {
+            if let Some(spawn) = action.to_spawn() {
+                emit(model, &spawn);
+            }
+        }
+        Action::Impulse { x, y, strength } => impulse(model, waves::Point::new(x, y), strength),
+        Action::Menu { button } => match MenuButton::from_name(&button) {
+            Some(button) => press_menu_button(model, button),
+            None => eprintln!("Ignoring unknown menu button '{}' in session", button),
+        },
+        Action::Rain { enabled, rate, wind } => set_rain(model, enabled, rate, wind),
+        Action::AddObstacle { obstacle } => match obstacle::parse_line(&obstacle) {
+            Ok(obstacle) => add_obstacle(model, obstacle),
+            Err(error) => eprintln!("Ignoring obstacle '{}' in session: {}", obstacle, error),
+        },
+        Action::MoveObstacle { index, x, y } => move_obstacle(model, index, waves::Point::new(x, y)),
+        Action::DeleteObstacle { index } => delete_obstacle(model, index),
+    }
+}
+
+// Blend from the current background to `background`
+fn switch_background(model: &mut Model, background: BackgroundType) {
+    if background != model.menu.scene.background_type {
+        model.crossfade = Some(Crossfade { from: model.menu.scene.background_type, progress: 0.0 });
+        model.menu.scene.background_type = background;
+    }
+}
+
+// Real input ends attract mode and brings back the background from before it
+fn wake(model: &mut Model) {
+    if model.attract.input() && let Some(background) = model.resting_background.take() {
+        switch_background(model, background);
+    }
+}
+
+// Spawn the first ripple of a press and keep spawning that kind while held
+fn start_ripples(app: &App, model: &mut Model, position: waves::Point, kind: RippleKind) {
+    let spawn = model.emitter.press(position, kind, app.time);
+    match model.menu.mode {
+        SimulationMode::Rings => emit(model, &spawn),
+        SimulationMode::Waves => impulse(model, position, waves::wave::IMPULSE_STRENGTH),
+    }
+}
+
 fn event(app: &App, model: &mut Model, event: Event) {
+    if let Event::WindowEvent { simple: Some(MousePressed(_) | MouseMoved(_) | MouseWheel(..) | KeyPressed(_)), .. } = event {
+        wake(model);
+    }
+
     match event {
         Event::WindowEvent { simple: Some(MousePressed(button)), .. } => {
             if button == MouseButton::Left {
@@ -198,65 +542,173 @@ fn event(app: &App, model: &mut Model, event: Event) {
                     return;
                 }
                 
-                // Check if user clicked on wobble toggle button
-                if model.menu.is_in_wobble_button(mouse_pos) {
-                    model.menu.wobble_enabled = !model.menu.wobble_enabled;
+                // Check if user clicked on one of the menu buttons
+                if let Some(menu_button) = model.menu.button_at(mouse_pos) {
+                    press_menu_button(model, menu_button);
                     return;
                 }
-                
-                // Check if user clicked on fade toggle button
-                if model.menu.is_in_fade_button(mouse_pos) {
-                    model.menu.fade_enabled = !model.menu.fade_enabled;
+
+                // Check if user picked an obstacle from the list
+                if let Some(row) = model.menu.list_row_at(mouse_pos, model.field.obstacles.len()) {
+                    model.menu.selected_obstacle = Some(row);
                     return;
                 }
 
-                if model.menu.is_in_bg_type_button(mouse_pos) {
-                    // Cycle through background types
-                    model.menu.background_type = match model.menu.background_type {
-                        BackgroundType::None => BackgroundType::Water,
-                        BackgroundType::Water => BackgroundType::Lava,
-                        BackgroundType::Lava => BackgroundType::Radial,
-                        BackgroundType::Radial => BackgroundType::None,
-                    };
+                let position = effects::from_point2(mouse_pos);
+
+                // Place the selected obstacle after pressing Move
+                if model.menu.moving_obstacle {
+                    if let Some(selected) = model.menu.selected_obstacle {
+                        move_obstacle(model, selected, position);
+                    }
+                    model.menu.moving_obstacle = false;
+                    return;
+                }
+
+                // Shift+click drops a rock, Ctrl+click adds a wall vertex
+                if app.keys.mods.shift() {
+                    add_obstacle(model, Obstacle::Circle { center: position, radius: OBSTACLE_RADIUS });
+                    return;
+                }
+                if app.keys.mods.ctrl() {
+                    add_polygon_vertex(model, position);
                     return;
                 }
                 
                 // If not clicking on UI, start creating ripples
-                model.mouse_down = true;
-                model.ripples.push(Ripple::new(mouse_pos, app.time));
-                model.last_ripple_time = app.time;
+                start_ripples(app, model, position, RippleKind::Drop);
+            } else if button == MouseButton::Right {
+                // Right button makes big, slow swells (outside the menu only)
+                let mouse_pos = app.mouse.position();
+                if !model.menu.contains(mouse_pos, model.field.obstacles.len()) {
+                    start_ripples(app, model, effects::from_point2(mouse_pos), RippleKind::Swell);
+                }
             }
         },
         Event::WindowEvent { simple: Some(MouseReleased(button)), .. } => {
-            if button == MouseButton::Left {
-                model.mouse_down = false;
+            if button == MouseButton::Left || button == MouseButton::Right {
+                model.emitter.release();
             }
         },
+        Event::WindowEvent { simple: Some(KeyPressed(key)), .. } => {
+            match key {
+                Key::Return => close_pending_polygon(model),
+                Key::Escape => {
+                    model.pending_polygon.clear();
+                    model.menu.moving_obstacle = false;
+                }
+                Key::Delete => delete_selected_obstacle(model),
+                // R toggles rain, up/down change its rate and left/right the wind
+                Key::R | Key::Up | Key::Down | Key::Right | Key::Left => {
+                    let (mut enabled, mut rate, mut wind) = (model.menu.rain_enabled, model.menu.rain_rate, model.menu.wind);
+                    match key {
+                        Key::R => enabled = !enabled,
+                        Key::Up => rate = ui::step_value(&ui::RAIN_RATES, rate, 1),
+                        Key::Down => rate = ui::step_value(&ui::RAIN_RATES, rate, -1),
+                        Key::Right => wind = ui::step_value(&ui::WIND_LEVELS, wind, 1),
+                        _ => wind = ui::step_value(&ui::WIND_LEVELS, wind, -1),
+                    }
+                    set_rain(model, enabled, rate, wind);
+                }
+                // Space plays or pauses the timeline, [ and ] seek and Home restarts it
+                Key::Space => {
+                    if let Some(timeline) = &mut model.timeline {
+                        timeline.toggle();
+                    }
+                }
+                Key::LBracket => {
+                    let time = model.timeline.as_ref().map_or(0.0, |timeline| timeline.clock() - TIMELINE_SEEK_STEP);
+                    seek_timeline(model, time);
+                }
+                Key::RBracket => {
+                    let time = model.timeline.as_ref().map_or(0.0, |timeline| timeline.clock() + TIMELINE_SEEK_STEP);
+                    seek_timeline(model, time);
+                }
+                Key::Home => seek_timeline(model, 0.0),
+                // S saves the current frame as an SVG
+                Key::S => export_svg(model, effects::window_bounds(app)),
+                _ => {}
+            }
+        },
+        // Dropping an obstacle file onto the window loads it
+        Event::WindowEvent { simple: Some(DroppedFile(path)), .. } => {
+            load_obstacles(model, &path);
+        },
         _ => {}
     }
 }
 
+// Add the background, blending between two while switching: the new one
+// fades in over the old one (or the old one fades out when switching to none)
+fn draw_background(out: &mut Vec<waves::Primitive>, model: &Model, bounds: waves::Bounds, alpha: f32) {
+    let mut rng = random::seeded(model.frame_seed);
+    let (noise, time) = (&model.noise, model.time);
+    match &model.crossfade {
+        Some(crossfade) if model.menu.scene.background_type == BackgroundType::None => {
+            background::draw(out, crossfade.from, bounds, noise, time, alpha * (1.0 - crossfade.progress), &mut rng);
+        }
+        Some(crossfade) => {
+            background::draw(out, crossfade.from, bounds, noise, time, alpha, &mut rng);
+            background::draw(out, model.menu.scene.background_type, bounds, noise, time, alpha * crossfade.progress, &mut rng);
+        }
+        None => background::draw(out, model.menu.scene.background_type, bounds, noise, time, alpha, &mut rng),
+    }
+}
+
+// Save the frame on screen as an SVG named after the seed and time
+fn export_svg(model: &Model, bounds: waves::Bounds) {
+    let mut backdrop = Vec::new();
+    draw_background(&mut backdrop, model, bounds, 1.0);
+    let style = model.menu.scene.ring_style(model.time, model.noise);
+    let reflect = model.menu.scene.reflect();
+    let path = std::path::PathBuf::from(format!("waves-{}-{:.2}s.svg", model.field.seed(), model.time));
+    let saved = SvgDocument::frame(&model.field, &style, bounds, reflect, &backdrop, BackgroundStyle::Gradient)
+        .and_then(|document| document.save(&path));
+    match saved {
+        Ok(()) => println!("Saved {}", path.display()),
+        Err(error) => eprintln!("Could not export SVG: {}", error),
+    }
+}
+
 fn view(app: &App, model: &Model, frame: Frame) {
     let draw = app.draw();
-    
-    // Draw background
-    draw.background().color(BLACK);
+    let bounds = effects::window_bounds(app);
+    let mut primitives = Vec::new();
 
-    match model.menu.background_type {
-        BackgroundType::Water => effects::draw_water_background(&draw, app, model.noise, model.time),
-        BackgroundType::Lava => effects::draw_lava_background(&draw, app, model.noise, model.time),
-        BackgroundType::Radial => effects::draw_radial_background(&draw, app, model.noise, model.time),
-        BackgroundType::None => {}, // No background
-    }
+    // Draw background. In trails mode the previous frame is faded rather
+    // than cleared, and the background goes on top as a translucent layer.
+    let alpha = if model.menu.scene.trails_enabled && app.elapsed_frames() > 1 {
+        let alpha = 1.0 - (-model.frame_delta / model.menu.scene.trail_persistence).exp();
+        scene::fade(&mut primitives, bounds, alpha);
+        alpha
+    } else {
+        draw.background().color(BLACK);
+        1.0
+    };
 
-    // Draw all ripples
-    for ripple in &model.ripples {
-        ripple.draw(&draw, app, model.time, model.menu.wobble_enabled, model.menu.fade_enabled);
+    draw_background(&mut primitives, model, bounds, alpha);
+
+    match model.menu.mode {
+        // Draw all ripples
+        SimulationMode::Rings => {
+            let style = model.menu.scene.ring_style(model.time, model.noise);
+            let reflect = model.menu.scene.reflect();
+            scene::ripples(&mut primitives, &model.field, &style, bounds, reflect);
+            effects::draw_primitives(&draw, &primitives);
+        }
+        // Shade the wave height field on top of the background
+        SimulationMode::Waves => {
+            effects::draw_primitives(&draw, &primitives);
+            effects::draw_wave_surface(&draw, &model.waves, model.menu.scene.background_type != BackgroundType::None);
+        }
     }
     
+    // Draw obstacles over the water
+    effects::draw_obstacles(&draw, &model.field.obstacles, model.menu.selected_obstacle, &model.pending_polygon);
+
     // Draw the menu
-    model.menu.draw(&draw);
+    model.menu.draw(&draw, &model.field);
 
     // Render everything
     draw.to_frame(app, &frame).unwrap();
-}
\ No newline at end of file
+}
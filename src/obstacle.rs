@@ -0,0 +1,286 @@
+use std::fs;
+use std::path::Path;
+
+use crate::geom::Point;
+use crate::reflection::Mirror;
+
+// Occlusion settings
+pub const DIFFRACTION_SPREAD: f32 = 3.0;    // Arc length (times sqrt of ring radius) that bends into a shadow
+pub const DIFFRACTION_STRENGTH: f32 = 0.6;  // Opacity of the diffracted wave right at the shadow edge
+
+// A rock or wall that ripples cannot pass through
+#[derive(Clone, Debug, PartialEq)]
+pub enum Obstacle {
+    Circle { center: Point, radius: f32 },
+    Polygon { points: Vec<Point> },
+}
+
+// Check which side of the line a-b the point p lies on
+fn cross(a: Point, b: Point, p: Point) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+// Check if segments a-b and c-d properly cross each other
+fn segments_cross(a: Point, b: Point, c: Point, d: Point) -> bool {
+    let d1 = cross(c, d, a);
+    let d2 = cross(c, d, b);
+    let d3 = cross(a, b, c);
+    let d4 = cross(a, b, d);
+    (d1 > 0.0) != (d2 > 0.0) && (d3 > 0.0) != (d4 > 0.0)
+}
+
+// Distance from p to the closest point of segment a-b
+fn distance_to_segment(p: Point, a: Point, b: Point) -> f32 {
+    let (abx, aby) = (b.x - a.x, b.y - a.y);
+    let length_squared = abx * abx + aby * aby;
+    if length_squared == 0.0 {
+        return p.distance(a);
+    }
+    let t = (((p.x - a.x) * abx + (p.y - a.y) * aby) / length_squared).clamp(0.0, 1.0);
+    p.distance(Point::new(a.x + t * abx, a.y + t * aby))
+}
+
+impl Obstacle {
+    // Center of the obstacle (vertex average for polygons)
+    pub fn center(&self) -> Point {
+        match self {
+            Obstacle::Circle { center, .. } => *center,
+            Obstacle::Polygon { points } => {
+                let count = points.len().max(1) as f32;
+                let (x, y) = points.iter().fold((0.0, 0.0), |(x, y), p| (x + p.x, y + p.y));
+                Point::new(x / count, y / count)
+            }
+        }
+    }
+
+    // Move the obstacle so its center lands on `position`
+    pub fn move_to(&mut self, position: Point) {
+        let old = self.center();
+        let (dx, dy) = (position.x - old.x, position.y - old.y);
+        match self {
+            Obstacle::Circle { center, .. } => *center = position,
+            Obstacle::Polygon { points } => {
+                for p in points.iter_mut() {
+                    p.x += dx;
+                    p.y += dy;
+                }
+            }
+        }
+    }
+
+    // Check if a point lies inside the obstacle
+    pub fn contains(&self, point: Point) -> bool {
+        match self {
+            Obstacle::Circle { center, radius } => center.distance(point) <= *radius,
+            Obstacle::Polygon { points } => {
+                // Even-odd ray casting
+                let mut inside = false;
+                let mut j = points.len().wrapping_sub(1);
+                for i in 0..points.len() {
+                    let (a, b) = (points[i], points[j]);
+                    if (a.y > point.y) != (b.y > point.y)
+                        && point.x < (b.x - a.x) * (point.y - a.y) / (b.y - a.y) + a.x
+                    {
+                        inside = !inside;
+                    }
+                    j = i;
+                }
+                inside
+            }
+        }
+    }
+
+    // Check if the straight path from `from` to `to` is blocked by the obstacle
+    pub fn blocks(&self, from: Point, to: Point) -> bool {
+        match self {
+            Obstacle::Circle { center, radius } => distance_to_segment(*center, from, to) < *radius,
+            Obstacle::Polygon { points } => {
+                self.contains(to) || (0..points.len()).any(|i| {
+                    segments_cross(from, to, points[i], points[(i + 1) % points.len()])
+                })
+            }
+        }
+    }
+
+    // The same obstacle seen through a mirror (for reflected wave paths)
+    pub fn mirrored(&self, mirror: &Mirror) -> Obstacle {
+        match self {
+            Obstacle::Circle { center, radius } => Obstacle::Circle { center: mirror.apply(*center), radius: *radius },
+            Obstacle::Polygon { points } => Obstacle::Polygon { points: points.iter().map(|&p| mirror.apply(p)).collect() },
+        }
+    }
+
+    // The obstacle as a line of an obstacle file, read back by `parse_line`
+    pub fn to_line(&self) -> String {
+        match self {
+            Obstacle::Circle { center, radius } => format!("circle {} {} {}", center.x, center.y, radius),
+            Obstacle::Polygon { points } => {
+                let coordinates: Vec<String> = points.iter().map(|p| format!("{} {}", p.x, p.y)).collect();
+                format!("polygon {}", coordinates.join(" "))
+            }
+        }
+    }
+
+    // Short human readable summary, used by the obstacle list
+    pub fn describe(&self) -> String {
+        let center = self.center();
+        match self {
+            Obstacle::Circle { radius, .. } => format!("Rock ({:.0}, {:.0}) r{:.0}", center.x, center.y, radius),
+            Obstacle::Polygon { points } => format!("Wall ({:.0}, {:.0}) {} pts", center.x, center.y, points.len()),
+        }
+    }
+}
+
+// How visible each point of a closed ring outline is from its wave source.
+// Points whose straight path from `source` crosses an obstacle are in shadow;
+// near the shadow edge the wave bends in and fades out, approximating
+// diffraction. `mirror` is the image the source belongs to, so reflected
+// waves are also blocked by the obstacles on their way to the wall.
+pub fn visibility(source: Point, ring_radius: f32, points: &[Point], obstacles: &[Obstacle], mirror: &Mirror) -> Vec<f32> {
+    // Obstacles in the image's mirrored world, skipping any the wave started inside of
+    let mut blockers: Vec<Obstacle> = obstacles.to_vec();
+    if *mirror != Mirror::IDENTITY {
+        blockers.extend(obstacles.iter().map(|o| o.mirrored(mirror)));
+    }
+    blockers.retain(|o| !o.contains(source));
+
+    let lit: Vec<bool> = points.iter()
+        .map(|&p| !blockers.iter().any(|o| o.blocks(source, p)))
+        .collect();
+    if lit.iter().all(|&l| l) || lit.iter().all(|&l| !l) {
+        return lit.iter().map(|&l| if l { 1.0 } else { 0.0 }).collect();
+    }
+
+    // Arc length from each shadowed point to the nearest lit one, walking
+    // around the ring in both directions
+    let count = points.len();
+    let spacing = if count > 1 { points[0].distance(points[1]).max(f32::EPSILON) } else { 1.0 };
+    let bleed = DIFFRACTION_SPREAD * ring_radius.max(0.0).sqrt();
+    (0..count).map(|i| {
+        if lit[i] {
+            return 1.0;
+        }
+        let steps = (1..count)
+            .find(|&step| lit[(i + step) % count] || lit[(i + count - step) % count])
+            .unwrap_or(count);
+        let arc = steps as f32 * spacing;
+        DIFFRACTION_STRENGTH * (1.0 - arc / bleed).max(0.0)
+    }).collect()
+}
+
+// Parse obstacles from text, one per line in the format read by `parse_line`.
+// Blank lines and lines starting with '#' are ignored.
+pub fn parse(text: &str) -> Result<Vec<Obstacle>, String> {
+    let mut obstacles = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        obstacles.push(parse_line(line).map_err(|e| format!("line {}: {}", number + 1, e))?);
+    }
+    Ok(obstacles)
+}
+
+// Parse a single obstacle:
+//   circle <x> <y> <radius>
+//   polygon <x1> <y1> <x2> <y2> <x3> <y3> ...
+pub fn parse_line(line: &str) -> Result<Obstacle, String> {
+    let mut words = line.split_whitespace();
+    let kind = words.next().unwrap_or_default();
+    let values = words
+        .map(|w| w.parse::<f32>().map_err(|_| format!("'{}' is not a number", w)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match kind {
+        "circle" if values.len() == 3 => Ok(Obstacle::Circle {
+            center: Point::new(values[0], values[1]),
+            radius: values[2],
+        }),
+        "polygon" if values.len() >= 6 && values.len() % 2 == 0 => Ok(Obstacle::Polygon {
+            points: values.chunks(2).map(|xy| Point::new(xy[0], xy[1])).collect(),
+        }),
+        "circle" => Err("circle needs x, y and radius".to_string()),
+        "polygon" => Err("polygon needs at least three x y pairs".to_string()),
+        other => Err(format!("unknown obstacle '{}'", other)),
+    }
+}
+
+// Load obstacles from a file in the format accepted by `parse`
+pub fn load(path: &Path) -> Result<Vec<Obstacle>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square() -> Obstacle {
+        Obstacle::Polygon { points: vec![Point::new(-10.0, -10.0), Point::new(10.0, -10.0), Point::new(10.0, 10.0), Point::new(-10.0, 10.0)] }
+    }
+
+    #[test]
+    fn parses_circles_and_polygons() {
+        let text = "# a rock and a wall\ncircle 150 -80 40\n\n  polygon -10 -10 10 -10 10 10 -10 10\n";
+        assert_eq!(parse(text), Ok(vec![Obstacle::Circle { center: Point::new(150.0, -80.0), radius: 40.0 }, square()]));
+    }
+
+    #[test]
+    fn reports_the_line_of_a_bad_obstacle() {
+        assert_eq!(parse("circle 1 2 3\ncircle 1 2"), Err("line 2: circle needs x, y and radius".to_string()));
+        assert_eq!(parse("polygon 0 0 1 1"), Err("line 1: polygon needs at least three x y pairs".to_string()));
+        assert_eq!(parse("polygon 0 0 1 1 2"), Err("line 1: polygon needs at least three x y pairs".to_string()));
+        assert_eq!(parse("circle 1 two 3"), Err("line 1: 'two' is not a number".to_string()));
+        assert_eq!(parse("\n\nsquare 1 2 3"), Err("line 3: unknown obstacle 'square'".to_string()));
+    }
+
+    #[test]
+    fn moving_keeps_the_shape() {
+        let mut wall = square();
+        wall.move_to(Point::new(100.0, 50.0));
+        assert_eq!(wall.center(), Point::new(100.0, 50.0));
+        assert!(wall.contains(Point::new(109.0, 41.0)));
+        assert!(!wall.contains(Point::new(0.0, 0.0)));
+    }
+
+    #[test]
+    fn blocks_paths_that_cross_it() {
+        let rock = Obstacle::Circle { center: Point::default(), radius: 10.0 };
+        assert!(rock.blocks(Point::new(-50.0, 0.0), Point::new(50.0, 0.0)));
+        assert!(!rock.blocks(Point::new(-50.0, 20.0), Point::new(50.0, 20.0)));
+        assert!(square().blocks(Point::new(-50.0, 5.0), Point::new(50.0, 5.0)));
+        assert!(!square().blocks(Point::new(-50.0, 15.0), Point::new(50.0, 15.0)));
+    }
+
+    #[test]
+    fn casts_a_shadow_that_fades_in_at_the_edges() {
+        let rock = Obstacle::Circle { center: Point::new(50.0, 0.0), radius: 10.0 };
+        let ring: Vec<Point> = (0..72)
+            .map(|i| i as f32 * std::f32::consts::TAU / 72.0)
+            .map(|angle| Point::new(100.0 * angle.cos(), 100.0 * angle.sin()))
+            .collect();
+        let visibility = visibility(Point::default(), 100.0, &ring, &[rock], &Mirror::IDENTITY);
+        assert_eq!(visibility[18], 1.0, "the far side of the ring is lit");
+        assert!(visibility[0] < DIFFRACTION_STRENGTH, "straight behind the rock is in shadow");
+        // Just inside the shadow the wave bends in
+        let edge = (1..18).find(|&i| visibility[i] == 1.0).unwrap() - 1;
+        assert!(visibility[edge] > visibility[0] && visibility[edge] <= DIFFRACTION_STRENGTH);
+        assert_eq!(visibility[72 - edge], visibility[edge], "the shadow is symmetric");
+    }
+
+    #[test]
+    fn waves_starting_inside_an_obstacle_are_not_blocked_by_it() {
+        let rock = Obstacle::Circle { center: Point::default(), radius: 10.0 };
+        let points = [Point::new(50.0, 0.0), Point::new(0.0, 50.0)];
+        assert_eq!(visibility(Point::default(), 50.0, &points, &[rock], &Mirror::IDENTITY), [1.0, 1.0]);
+    }
+
+    #[test]
+    fn lines_read_back_as_the_same_obstacle() {
+        let rock = Obstacle::Circle { center: Point::new(0.1, -2.75), radius: 1.0 / 3.0 };
+        for obstacle in [rock, square()] {
+            assert_eq!(parse_line(&obstacle.to_line()), Ok(obstacle));
+        }
+    }
+}
@@ -0,0 +1,526 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rand::Rng;
+
+use crate::color::{self, Color};
+use crate::random;
+
+// Folder inside the user's config directory that palette files are loaded from
+pub const PALETTE_DIR: &str = "waves/palettes";
+
+// A named set of ring colors. `weights` gives each color's share under
+// `Selection::Weighted`; colors without a weight count as 1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Palette {
+    pub name: String,
+    pub colors: Vec<Color>,
+    pub weights: Vec<f32>,
+}
+
+impl Palette {
+    pub fn new(name: impl Into<String>, colors: Vec<Color>) -> Self {
+        Self { name: name.into(), colors, weights: Vec::new() }
+    }
+
+    pub fn with_weights(mut self, weights: Vec<f32>) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    fn weight(&self, index: usize) -> f32 {
+        self.weights.get(index).copied().unwrap_or(1.0).max(0.0)
+    }
+}
+
+// Palettes that are always available, in menu order
+pub fn builtin() -> Vec<Palette> {
+    let hex = |values: &[u32]| values.iter()
+        .map(|&v| Color::new((v >> 16) as u8, (v >> 8) as u8, v as u8))
+        .collect::<Vec<_>>();
+    vec![
+        // The original nannou named colors
+        Palette::new("classic", vec![
+            Color::new(255, 222, 173),  // Navajo white
+            Color::new(250, 250, 210),  // Light goldenrod yellow
+            Color::new(250, 235, 215),  // Antique white
+            Color::new(255, 235, 205),  // Blanched almond
+            Color::new(255, 255, 255),  // White
+            Color::new(224, 255, 255),  // Light cyan
+            Color::new(135, 206, 235),  // Sky blue
+            Color::new(176, 196, 222),  // Light steel blue
+        ]),
+        Palette::new("pastel", hex(&[0xffb3ba, 0xffdfba, 0xffffba, 0xbaffc9, 0xbae1ff, 0xe0bbe4])),
+        Palette::new("ocean", hex(&[0x03045e, 0x0077b6, 0x00b4d8, 0x90e0ef, 0xcaf0f8, 0xffffff]))
+            .with_weights(vec![0.5, 1.0, 2.0, 2.0, 1.5, 0.5]),
+        Palette::new("neon", hex(&[0xff00ff, 0x00ffff, 0x39ff14, 0xfff01f, 0xff3131, 0xbc13fe])),
+        Palette::new("monochrome", hex(&[0xffffff, 0xd0d0d0, 0xa0a0a0, 0x707070]))
+            .with_weights(vec![3.0, 2.0, 1.0, 0.5]),
+        // Okabe-Ito colors, distinguishable with the common color vision deficiencies
+        Palette::new("colour-blind", hex(&[0xe69f00, 0x56b4e9, 0x009e73, 0xf0e442, 0x0072b2, 0xd55e00, 0xcc79a7])),
+    ]
+}
+
+// How ring colors are drawn from the active palette
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Selection {
+    #[default]
+    Uniform,                                // Every color equally likely
+    Sequential,                             // Colors in palette order, wrapping around
+    Weighted,                               // Random, in proportion to the palette's weights
+}
+
+impl Selection {
+    pub const ALL: [Selection; 3] = [Selection::Uniform, Selection::Sequential, Selection::Weighted];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Selection::Uniform => "UNIFORM",
+            Selection::Sequential => "SEQUENCE",
+            Selection::Weighted => "WEIGHTED",
+        }
+    }
+
+    pub fn next(self) -> Selection {
+        let index = Selection::ALL.iter().position(|&s| s == self).unwrap_or(0);
+        Selection::ALL[(index + 1) % Selection::ALL.len()]
+    }
+}
+
+// Picks colors from a palette according to a `Selection`
+#[derive(Clone, Debug, Default)]
+pub struct ColorPicker {
+    pub selection: Selection,
+    cursor: usize,                          // Next color for sequential selection
+}
+
+impl ColorPicker {
+    pub fn new(selection: Selection) -> Self {
+        Self { selection, cursor: 0 }
+    }
+
+    // Start sequential selection over from the first color
+    pub fn reset(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn pick(&mut self, palette: &Palette, rng: &mut impl Rng) -> Color {
+        let count = palette.colors.len();
+        if count == 0 {
+            return Color::new(255, 255, 255);
+        }
+        let index = match self.selection {
+            Selection::Uniform => random::index(rng, count),
+            Selection::Sequential => {
+                let index = self.cursor % count;
+                self.cursor = index + 1;
+                index
+            }
+            Selection::Weighted => {
+                let total: f32 = (0..count).map(|i| palette.weight(i)).sum();
+                if !total.is_finite() || total <= 0.0 {
+                    // Weights set in code may not add up to anything usable
+                    random::index(rng, count)
+                } else {
+                    let mut target = random::range(rng, 0.0, total);
+                    (0..count)
+                        .find(|&i| {
+                            target -= palette.weight(i);
+                            target < 0.0
+                        })
+                        .unwrap_or(count - 1)
+                }
+            }
+        };
+        palette.colors[index]
+    }
+}
+
+// Built-in palettes plus any loaded from files, one of them active
+#[derive(Clone, Debug)]
+pub struct PaletteRegistry {
+    palettes: Vec<Palette>,
+    active: usize,
+}
+
+impl Default for PaletteRegistry {
+    fn default() -> Self {
+        Self { palettes: builtin(), active: 0 }
+    }
+}
+
+impl PaletteRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn palettes(&self) -> &[Palette] {
+        &self.palettes
+    }
+
+    pub fn active(&self) -> &Palette {
+        &self.palettes[self.active]
+    }
+
+    // Add a palette, replacing any with the same name
+    pub fn add(&mut self, palette: Palette) {
+        match self.palettes.iter().position(|p| p.name == palette.name) {
+            Some(index) => self.palettes[index] = palette,
+            None => self.palettes.push(palette),
+        }
+    }
+
+    // Make the named palette active. Returns false if there is none by that name.
+    pub fn select(&mut self, name: &str) -> bool {
+        match self.palettes.iter().position(|p| p.name == name) {
+            Some(index) => {
+                self.active = index;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Activate the palette after the current one, wrapping around
+    pub fn select_next(&mut self) {
+        self.active = (self.active + 1) % self.palettes.len();
+    }
+
+    // Load every palette file in `dir`. Files that fail to load are
+    // reported in the returned list and skipped.
+    pub fn load_dir(&mut self, dir: &Path) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut paths = match fs::read_dir(dir) {
+            Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect::<Vec<_>>(),
+            Err(_) => return errors,            // No palette folder is not an error
+        };
+        paths.sort();
+        for path in paths.iter().filter(|p| p.is_file()) {
+            match load(path) {
+                Ok(palette) => self.add(palette),
+                Err(e) => errors.push(e),
+            }
+        }
+        errors
+    }
+}
+
+// Default palette folder: <config dir>/waves/palettes
+pub fn config_dir() -> Option<PathBuf> {
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    base.map(|base| base.join(PALETTE_DIR))
+}
+
+// Load a palette file, picking the format from its extension:
+// `.gpl` (GIMP), `.ase` (Adobe Swatch Exchange), anything else a hex list
+pub fn load(path: &Path) -> Result<Palette, String> {
+    let name = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    let result = if extension == "ase" {
+        fs::read(path).map_err(|e| e.to_string()).and_then(|bytes| parse_ase(&name, &bytes))
+    } else {
+        fs::read_to_string(path).map_err(|e| e.to_string()).and_then(|text| match extension.as_str() {
+            "gpl" => parse_gpl(&name, &text),
+            _ => parse_hex(&name, &text),
+        })
+    };
+    result.map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// Parse a GIMP palette:
+//   GIMP Palette
+//   Name: Sunset
+//   # comment
+//   255 128   0   Orange
+// The `Name:` header replaces `name` if present.
+pub fn parse_gpl(name: &str, text: &str) -> Result<Palette, String> {
+    let mut lines = text.lines();
+    if lines.next().map(str::trim) != Some("GIMP Palette") {
+        return Err("missing 'GIMP Palette' header".to_string());
+    }
+    let mut palette = Palette::new(name, Vec::new());
+    for (number, line) in lines.enumerate() {
+        let line = line.trim();
+        if let Some(value) = line.strip_prefix("Name:") {
+            palette.name = value.trim().to_string();
+            continue;
+        }
+        if line.is_empty() || line.starts_with('#') || line.starts_with("Columns:") {
+            continue;
+        }
+        let channels = line.split_whitespace()
+            .take(3)
+            .map(|w| w.parse::<u8>().map_err(|_| format!("line {}: '{}' is not a color channel", number + 2, w)))
+            .collect::<Result<Vec<_>, _>>()?;
+        match channels[..] {
+            [red, green, blue] => palette.colors.push(Color::new(red, green, blue)),
+            _ => return Err(format!("line {}: expected red, green and blue", number + 2)),
+        }
+    }
+    non_empty(palette)
+}
+
+// Parse a list of hex colors, separated by whitespace, commas or newlines.
+// A color can carry a weight after a '*':
+//   #ff8800*3
+//   00aaff, #fff, 112233*0.5
+// Lines starting with "//" or ";", or '#' not followed by a color, are comments.
+pub fn parse_hex(name: &str, text: &str) -> Result<Palette, String> {
+    let mut palette = Palette::new(name, Vec::new());
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with("//") || line.starts_with(';') {
+            continue;
+        }
+        for word in line.split(|c: char| c.is_whitespace() || c == ',').filter(|w| !w.is_empty()) {
+            let (color, weight) = match word.split_once('*') {
+                Some((color, weight)) => (color, Some(weight)),
+                None => (word, None),
+            };
+            if let Some(color) = parse_hex_color(color) {
+                let weight = weight.map_or(Ok(1.0), parse_weight).map_err(|e| format!("line {}: {}", number + 1, e))?;
+                palette.colors.push(color);
+                palette.weights.push(weight);
+            } else if word.starts_with('#') {
+                break;                          // Rest of the line is a comment
+            } else {
+                return Err(format!("line {}: '{}' is not a hex color", number + 1, word));
+            }
+        }
+    }
+    non_empty(palette)
+}
+
+// A color's share under weighted picking: finite and above zero
+fn parse_weight(text: &str) -> Result<f32, String> {
+    text.parse::<f32>()
+        .ok()
+        .filter(|weight| weight.is_finite() && *weight > 0.0)
+        .ok_or_else(|| format!("weight '{}' is not a positive number", text))
+}
+
+// "#rrggbb", "rrggbb", "#rgb" or "rgb"
+fn parse_hex_color(word: &str) -> Option<Color> {
+    let digits = word.strip_prefix('#').unwrap_or(word);
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |s: &str| u8::from_str_radix(s, 16).ok();
+    match digits.len() {
+        6 => Some(Color::new(channel(&digits[0..2])?, channel(&digits[2..4])?, channel(&digits[4..6])?)),
+        3 => {
+            let short = |i: usize| channel(&digits[i..i + 1]).map(|v| v * 17);
+            Some(Color::new(short(0)?, short(1)?, short(2)?))
+        }
+        _ => None,
+    }
+}
+
+// Parse an Adobe Swatch Exchange file. RGB, CMYK, gray and Lab swatches are
+// converted to sRGB; groups are flattened into one palette.
+pub fn parse_ase(name: &str, bytes: &[u8]) -> Result<Palette, String> {
+    let mut reader = Reader { bytes, position: 0 };
+    if reader.take(4)? != b"ASEF" {
+        return Err("not an ASE file".to_string());
+    }
+    reader.take(4)?;                            // Version
+    let block_count = reader.u32()?;
+    let mut palette = Palette::new(name, Vec::new());
+
+    for _ in 0..block_count {
+        let kind = reader.u16()?;
+        let length = reader.u32()? as usize;
+        let mut block = Reader { bytes: reader.take(length)?, position: 0 };
+        if kind != 0x0001 {
+            continue;                           // Group start or end
+        }
+        let name_length = block.u16()? as usize;
+        block.take(name_length * 2)?;           // UTF-16 swatch name
+        let model = block.take(4)?;
+        let values = match model {
+            b"RGB " | b"LAB " => 3,
+            b"CMYK" => 4,
+            b"Gray" => 1,
+            _ => return Err(format!("unknown color model '{}'", String::from_utf8_lossy(model))),
+        };
+        let v = (0..values).map(|_| block.f32()).collect::<Result<Vec<_>, _>>()?;
+        let rgb = match model {
+            b"RGB " => [v[0], v[1], v[2]],
+            b"CMYK" => [0, 1, 2].map(|i| (1.0 - v[i]) * (1.0 - v[3])),
+            b"Gray" => [v[0]; 3],
+            _ => lab_to_srgb(v[0] * 100.0, v[1], v[2]),
+        };
+        palette.colors.push(Color::from_f32(rgb));
+    }
+    non_empty(palette)
+}
+
+// CIE Lab (D50, as used by ASE) to sRGB
+fn lab_to_srgb(lightness: f32, a: f32, b: f32) -> [f32; 3] {
+    let fy = (lightness + 16.0) / 116.0;
+    let (fx, fz) = (fy + a / 500.0, fy - b / 200.0);
+    let inverse = |t: f32| if t > 6.0 / 29.0 { t * t * t } else { 3.0 * (6.0f32 / 29.0).powi(2) * (t - 4.0 / 29.0) };
+    let (x, y, z) = (0.96422 * inverse(fx), inverse(fy), 0.82521 * inverse(fz));
+    // Bradford adapted XYZ (D50) to linear sRGB
+    color::linear_to_srgb([
+        3.133856 * x - 1.6168667 * y - 0.4906146 * z,
+        -0.9787684 * x + 1.9161415 * y + 0.0334540 * z,
+        0.0719453 * x - 0.2289914 * y + 1.4052427 * z,
+    ])
+}
+
+fn non_empty(palette: Palette) -> Result<Palette, String> {
+    if palette.colors.is_empty() {
+        return Err("palette has no colors".to_string());
+    }
+    Ok(palette)
+}
+
+// Big-endian cursor over the bytes of an ASE file
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self.position + count;
+        let slice = self.bytes.get(self.position..end).ok_or("file is truncated")?;
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let b = self.take(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.take(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn f32(&mut self) -> Result<f32, String> {
+        Ok(f32::from_bits(self.u32()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORANGE: Color = Color::new(255, 136, 0);
+
+    // One ASE color block with an empty swatch name
+    fn swatch(model: &[u8; 4], values: &[f32]) -> Vec<u8> {
+        let mut body = vec![0, 1, 0, 0];        // Name: one UTF-16 unit, the terminator
+        body.extend_from_slice(model);
+        for value in values {
+            body.extend_from_slice(&value.to_be_bytes());
+        }
+        body.extend_from_slice(&[0, 2]);        // Color type (normal)
+        let mut block = vec![0x00, 0x01];
+        block.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        block.extend(body);
+        block
+    }
+
+    fn ase(blocks: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = b"ASEF\x00\x01\x00\x00".to_vec();
+        bytes.extend_from_slice(&(blocks.len() as u32).to_be_bytes());
+        bytes.extend(blocks.concat());
+        bytes
+    }
+
+    #[test]
+    fn parses_gimp_palettes() {
+        let text = "GIMP Palette\nName: Sunset\nColumns: 4\n# warm\n255 136   0\tOrange\n  0 170 255 Sky\n";
+        let palette = parse_gpl("file", text).unwrap();
+        assert_eq!(palette.name, "Sunset");
+        assert_eq!(palette.colors, [ORANGE, Color::new(0, 170, 255)]);
+        assert!(parse_gpl("file", "255 0 0").unwrap_err().contains("header"));
+        assert_eq!(parse_gpl("file", "GIMP Palette\n255 0"), Err("line 2: expected red, green and blue".to_string()));
+        assert_eq!(parse_gpl("file", "GIMP Palette\n255 0 256"), Err("line 2: '256' is not a color channel".to_string()));
+        assert_eq!(parse_gpl("file", "GIMP Palette\n"), Err("palette has no colors".to_string()));
+    }
+
+    #[test]
+    fn parses_hex_lists_with_weights() {
+        let palette = parse_hex("sunset", "// Sunset\n#ff8800*3\n00aaff, #fff*0.5 # cool\n; end").unwrap();
+        assert_eq!(palette.name, "sunset");
+        assert_eq!(palette.colors, [ORANGE, Color::new(0, 170, 255), Color::new(255, 255, 255)]);
+        assert_eq!(palette.weights, [3.0, 1.0, 0.5]);
+        assert_eq!(parse_hex("x", "#ff8800 zz"), Err("line 1: 'zz' is not a hex color".to_string()));
+    }
+
+    #[test]
+    fn bare_numbers_are_colors() {
+        let palette = parse_hex("x", "ff0000, 112233, 445566\n123").unwrap();
+        assert_eq!(
+            palette.colors,
+            [Color::new(255, 0, 0), Color::new(0x11, 0x22, 0x33), Color::new(0x44, 0x55, 0x66), Color::new(0x11, 0x22, 0x33)]
+        );
+        assert_eq!(palette.weights, [1.0; 4]);
+        assert_eq!(parse_hex("x", "#ff8800 3"), Err("line 1: '3' is not a hex color".to_string()));
+    }
+
+    #[test]
+    fn rejects_weights_that_are_not_positive() {
+        for weight in ["1e39", "inf", "NaN", "0", "-1", "", "x3"] {
+            let error = parse_hex("x", &format!("#fff\n#ff8800*{}", weight)).unwrap_err();
+            assert_eq!(error, format!("line 2: weight '{}' is not a positive number", weight));
+        }
+    }
+
+    #[test]
+    fn parses_swatch_exchange_files() {
+        let bytes = ase(&[
+            vec![0xc0, 0x01, 0, 0, 0, 0],       // Group start
+            swatch(b"RGB ", &[1.0, 136.0 / 255.0, 0.0]),
+            swatch(b"CMYK", &[0.0, 1.0, 1.0, 0.0]),
+            swatch(b"Gray", &[0.5]),
+            swatch(b"LAB ", &[1.0, 0.0, 0.0]),
+            vec![0xc0, 0x02, 0, 0, 0, 0],       // Group end
+        ]);
+        let palette = parse_ase("swatches", &bytes).unwrap();
+        assert_eq!(palette.colors[..3], [ORANGE, Color::new(255, 0, 0), Color::new(128, 128, 128)]);
+        let white = palette.colors[3];
+        assert!(white.red >= 254 && white.green >= 254 && white.blue >= 254, "{:?}", white);
+    }
+
+    #[test]
+    fn rejects_broken_swatch_exchange_files() {
+        assert_eq!(parse_ase("x", b"GIMP"), Err("not an ASE file".to_string()));
+        assert!(parse_ase("x", &ase(&[swatch(b"HSV ", &[0.0, 0.0, 0.0])])).unwrap_err().contains("HSV"));
+        let mut truncated = ase(&[swatch(b"RGB ", &[1.0, 0.0, 0.0])]);
+        truncated.truncate(truncated.len() - 4);
+        assert!(parse_ase("x", &truncated).is_err());
+    }
+
+    #[test]
+    fn weighted_picks_skip_colors_without_weight() {
+        let palette = Palette::new("x", vec![ORANGE, Color::new(0, 0, 0)]).with_weights(vec![1.0, 0.0]);
+        let mut picker = ColorPicker::new(Selection::Weighted);
+        let mut rng = random::seeded(3);
+        assert!((0..50).all(|_| picker.pick(&palette, &mut rng) == ORANGE));
+    }
+
+    #[test]
+    fn weighted_picks_are_uniform_without_a_usable_total() {
+        let black = Color::new(0, 0, 0);
+        let mut picker = ColorPicker::new(Selection::Weighted);
+        let mut rng = random::seeded(3);
+        for weights in [vec![f32::INFINITY, 1.0], vec![f32::MAX, f32::MAX], vec![0.0, 0.0]] {
+            let palette = Palette::new("x", vec![ORANGE, black]).with_weights(weights);
+            let picks: Vec<Color> = (0..50).map(|_| picker.pick(&palette, &mut rng)).collect();
+            assert!(picks.contains(&ORANGE) && picks.contains(&black), "{:?}", palette.weights);
+        }
+    }
+}
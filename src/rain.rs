@@ -0,0 +1,179 @@
+use rand::Rng;
+
+use crate::emitter::{Spawn, MAX_STRETCH};
+use crate::geom::{Bounds, Point};
+use crate::random;
+use crate::ripple::RippleKind;
+
+// Rain settings
+pub const DEFAULT_RATE: f32 = 4.0;          // Drops per second
+pub const MAX_DROPS_PER_STEP: usize = 64;   // Upper bound on drops in one update, after a long pause
+pub const MIN_DROP_STRENGTH: f32 = 0.3;     // Smallest drop, as a ripple strength
+pub const MAX_DROP_STRENGTH: f32 = 2.5;     // Largest drop
+pub const MEAN_DROP_STRENGTH: f32 = 0.8;    // Typical drop
+pub const DROP_SPREAD: f32 = 0.45;          // Standard deviation of the log-normal sizes
+pub const WIND_SKEW: f32 = 1.5;             // How strongly full wind crowds drops towards the downwind edge
+pub const WIND_STRETCH: f32 = 0.5;          // Share of the drag stretch applied to drops at full wind
+
+// Distribution the size of each drop is drawn from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DropSize {
+    Uniform,                                // Any size between the smallest and largest equally likely
+    #[default]
+    LogNormal,                              // Most drops near the typical size, a few much bigger
+    Exponential,                            // Marshall-Palmer: many small drops, ever fewer big ones
+}
+
+impl DropSize {
+    pub const ALL: [DropSize; 3] = [DropSize::Uniform, DropSize::LogNormal, DropSize::Exponential];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DropSize::Uniform => "UNIFORM",
+            DropSize::LogNormal => "LOGNORMAL",
+            DropSize::Exponential => "EXPONENTIAL",
+        }
+    }
+
+    pub fn next(self) -> DropSize {
+        let index = DropSize::ALL.iter().position(|&size| size == self).unwrap_or(0);
+        DropSize::ALL[(index + 1) % DropSize::ALL.len()]
+    }
+
+    // Draw one drop strength, clamped to the smallest and largest drop
+    pub fn sample(self, rng: &mut impl Rng) -> f32 {
+        let strength = match self {
+            DropSize::Uniform => random::range(rng, MIN_DROP_STRENGTH, MAX_DROP_STRENGTH),
+            DropSize::LogNormal => MEAN_DROP_STRENGTH * (standard_normal(rng) * DROP_SPREAD).exp(),
+            DropSize::Exponential => MIN_DROP_STRENGTH + exponential(rng, 1.0 / (MEAN_DROP_STRENGTH - MIN_DROP_STRENGTH)),
+        };
+        strength.clamp(MIN_DROP_STRENGTH, MAX_DROP_STRENGTH)
+    }
+}
+
+// Spawns ripples at random places by itself. Arrivals form a Poisson
+// process: the waits between drops are exponentially distributed, so the
+// rain looks irregular while averaging `rate` drops per second.
+#[derive(Clone, Debug)]
+pub struct Rain {
+    pub enabled: bool,
+    pub rate: f32,                          // Average drops per second
+    pub size: DropSize,                     // Distribution of drop sizes
+    pub wind: f32,                          // -1 (full wind to the left) to 1 (full wind to the right)
+    countdown: f32,                         // Seconds until the next drop
+}
+
+impl Default for Rain {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rate: DEFAULT_RATE,
+            size: DropSize::default(),
+            wind: 0.0,
+            countdown: 0.0,
+        }
+    }
+}
+
+impl Rain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Advance by `dt` seconds. Returns the drops that fell inside `bounds`.
+    pub fn update(&mut self, dt: f32, bounds: Bounds, rng: &mut impl Rng) -> Vec<Spawn> {
+        if !self.enabled || self.rate <= 0.0 {
+            return Vec::new();
+        }
+        let mut drops = Vec::new();
+        self.countdown -= dt;
+        while self.countdown <= 0.0 {
+            if drops.len() < MAX_DROPS_PER_STEP {
+                drops.push(self.drop(bounds, rng));
+            }
+            self.countdown += exponential(rng, self.rate);
+        }
+        drops
+    }
+
+    // One drop somewhere in `bounds`, pushed downwind and stretched along the wind
+    fn drop(&self, bounds: Bounds, rng: &mut impl Rng) -> Spawn {
+        // Raising a uniform value to a power below 1 crowds it towards 1,
+        // above 1 towards 0, so the wind piles drops up on one side
+        let across = random::range(rng, 0.0, 1.0).powf((-self.wind * WIND_SKEW).exp2());
+        let position = Point::new(
+            bounds.left + across * bounds.width(),
+            random::range(rng, bounds.bottom, bounds.top),
+        );
+
+        let mut spawn = Spawn::new(position, RippleKind::Drop);
+        spawn.strength = self.size.sample(rng);
+        if self.wind != 0.0 {
+            spawn.stretch = 1.0 + (MAX_STRETCH - 1.0) * WIND_STRETCH * self.wind.abs();
+            spawn.direction = if self.wind > 0.0 { 0.0 } else { std::f32::consts::PI };
+        }
+        spawn
+    }
+}
+
+// Exponentially distributed value with the given rate (mean 1 / rate)
+fn exponential(rng: &mut impl Rng, rate: f32) -> f32 {
+    // 1 - u lies in (0, 1], so the logarithm stays finite
+    -(1.0 - random::range(rng, 0.0, 1.0)).ln() / rate
+}
+
+// Normally distributed value with mean 0 and standard deviation 1 (Box-Muller)
+fn standard_normal(rng: &mut impl Rng) -> f32 {
+    let radius = (-2.0 * (1.0 - random::range(rng, 0.0, 1.0)).ln()).sqrt();
+    radius * random::range(rng, 0.0, std::f32::consts::TAU).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNDS: Bounds = Bounds::new(-100.0, 100.0, -50.0, 50.0);
+
+    fn rain() -> Rain {
+        Rain { enabled: true, ..Rain::new() }
+    }
+
+    #[test]
+    fn drops_fall_at_the_average_rate() {
+        let mut rain = rain();
+        let mut rng = random::seeded(1);
+        let seconds = 500.0;
+        let count: usize = (0..(seconds * 60.0) as usize).map(|_| rain.update(1.0 / 60.0, BOUNDS, &mut rng).len()).sum();
+        let rate = count as f32 / seconds;
+        assert!((rate - DEFAULT_RATE).abs() < 0.2, "{} drops per second", rate);
+    }
+
+    #[test]
+    fn a_long_pause_drops_at_most_the_cap() {
+        let mut rain = rain();
+        let mut rng = random::seeded(1);
+        assert_eq!(rain.update(60.0, BOUNDS, &mut rng).len(), MAX_DROPS_PER_STEP);
+        // The backlog is gone rather than arriving on the next frames
+        assert!(rain.update(1.0 / 60.0, BOUNDS, &mut rng).len() <= 1);
+    }
+
+    #[test]
+    fn drops_land_inside_the_bounds_with_valid_sizes() {
+        let mut rng = random::seeded(2);
+        for (size, wind) in [(DropSize::Uniform, 0.0), (DropSize::LogNormal, 1.0), (DropSize::Exponential, -1.0)] {
+            let mut rain = Rain { size, wind, ..rain() };
+            for spawn in rain.update(20.0, BOUNDS, &mut rng) {
+                assert!(spawn.position.x >= BOUNDS.left && spawn.position.x <= BOUNDS.right);
+                assert!(spawn.position.y >= BOUNDS.bottom && spawn.position.y <= BOUNDS.top);
+                assert!((MIN_DROP_STRENGTH..=MAX_DROP_STRENGTH).contains(&spawn.strength));
+            }
+        }
+    }
+
+    #[test]
+    fn disabled_or_stopped_rain_drops_nothing() {
+        let mut rng = random::seeded(1);
+        assert!(Rain::new().update(10.0, BOUNDS, &mut rng).is_empty());
+        assert!(Rain { rate: 0.0, ..rain() }.update(10.0, BOUNDS, &mut rng).is_empty());
+    }
+}
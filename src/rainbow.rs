@@ -0,0 +1,99 @@
+use crate::color;
+
+// Hue offsets, in degrees
+pub const RIPPLE_HUE_STEP: f32 = 137.5;     // Between consecutive ripples (golden angle, so neighbours differ)
+pub const RING_HUE_STEP: f32 = 24.0;        // Between neighbouring rings of one ripple
+pub const MAX_CHROMA: f32 = 0.2;            // LCH chroma at full saturation (OKLCh units)
+
+// Color wheel the rainbow hue walks around
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HueModel {
+    #[default]
+    Hsl,                                    // Classic, vivid but uneven brightness across hues
+    Lch,                                    // OKLCh, even perceived brightness across hues
+}
+
+impl HueModel {
+    pub fn name(self) -> &'static str {
+        match self {
+            HueModel::Hsl => "HSL",
+            HueModel::Lch => "LCH",
+        }
+    }
+}
+
+// Ring colors taken from a hue that turns with time, ripple, ring and the
+// angle around the ring, so each circumference shows a rotating rainbow
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rainbow {
+    pub model: HueModel,
+    pub saturation: f32,                    // 0 (gray) to 1 (full color)
+    pub lightness: f32,                     // 0 (black) to 1 (white)
+    pub speed: f32,                         // Turns of the color wheel per second
+}
+
+impl Rainbow {
+    pub const DEFAULT: Rainbow = Rainbow {
+        model: HueModel::Hsl,
+        saturation: 0.8,
+        lightness: 0.6,
+        speed: 0.1,
+    };
+
+    // sRGB color (components in 0..1) at `degrees` around `ring` of the
+    // ripple numbered `ripple`
+    pub fn color(&self, time: f32, ripple: u64, ring: i16, degrees: f32) -> [f32; 3] {
+        let hue = (degrees
+            + time * self.speed * 360.0
+            + (ripple % 360) as f32 * RIPPLE_HUE_STEP
+            + ring as f32 * RING_HUE_STEP)
+            .rem_euclid(360.0);
+        match self.model {
+            HueModel::Hsl => color::hsl_to_srgb(hue, self.saturation, self.lightness),
+            HueModel::Lch => color::oklch_to_srgb(self.lightness, self.saturation * MAX_CHROMA, hue),
+        }
+    }
+}
+
+impl Default for Rainbow {
+    fn default() -> Self {
+        Rainbow::DEFAULT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VIVID: Rainbow = Rainbow { saturation: 1.0, lightness: 0.5, speed: 0.25, ..Rainbow::DEFAULT };
+
+    fn close(a: [f32; 3], b: [f32; 3]) -> bool {
+        (0..3).all(|i| (a[i] - b[i]).abs() < 1e-4)
+    }
+
+    #[test]
+    fn the_hue_turns_with_time() {
+        assert!(close(VIVID.color(0.0, 0, 0, 0.0), [1.0, 0.0, 0.0]));
+        // A quarter turn per second
+        assert!(close(VIVID.color(1.0, 0, 0, 0.0), [0.5, 1.0, 0.0]));
+        assert!(close(VIVID.color(2.0, 0, 0, 0.0), [0.0, 1.0, 1.0]));
+        assert!(close(VIVID.color(4.0, 0, 0, 0.0), VIVID.color(0.0, 0, 0, 0.0)));
+    }
+
+    #[test]
+    fn ripples_and_rings_are_offset_around_the_wheel() {
+        assert!(close(VIVID.color(0.0, 0, 1, 0.0), VIVID.color(0.0, 0, 0, RING_HUE_STEP)));
+        assert!(close(VIVID.color(0.0, 1, 0, 0.0), VIVID.color(0.0, 0, 0, RIPPLE_HUE_STEP)));
+        assert!(close(VIVID.color(0.0, 360, 0, 0.0), VIVID.color(0.0, 0, 0, 0.0)));
+    }
+
+    #[test]
+    fn lch_hues_stay_in_gamut() {
+        let lch = Rainbow { model: HueModel::Lch, ..Rainbow::DEFAULT };
+        for step in 0..36 {
+            let color = lch.color(step as f32, 0, 0, 0.0);
+            assert!(color.iter().all(|c| (0.0..=1.0).contains(c)));
+        }
+        assert!(!close(lch.color(0.0, 0, 0, 0.0), lch.color(0.0, 0, 0, 180.0)));
+    }
+}
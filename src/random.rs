@@ -0,0 +1,112 @@
+use noise::Perlin;
+#[cfg(not(target_os = "macos"))]
+use noise::Seedable;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+// Random number generator shared by everything that needs randomness
+pub type SimRng = StdRng;
+
+// Generator for a given seed; the same seed always gives the same sequence
+pub fn seeded(seed: u64) -> SimRng {
+    StdRng::seed_from_u64(seed)
+}
+
+// Fresh seed for runs that did not ask for a specific one
+pub fn random_seed() -> u64 {
+    rand::random()
+}
+
+// Perlin noise generator derived from a run's seed
+pub fn seeded_noise(seed: u64) -> Perlin {
+    #[cfg(target_os = "macos")]
+    let noise_gen = Perlin::new(seed as u32);
+    #[cfg(not(target_os = "macos"))]
+    let noise_gen = Perlin::new().set_seed(seed as u32);
+    noise_gen
+}
+
+// Uniform value in low..high (returns `low` for an empty range)
+pub fn range(rng: &mut impl Rng, low: f32, high: f32) -> f32 {
+    if high <= low {
+        return low;
+    }
+    #[cfg(target_os = "macos")]
+    let value = rng.gen_range(low..high);
+    #[cfg(not(target_os = "macos"))]
+    let value = rng.gen_range(low, high);
+    value
+}
+
+// Uniform index in 0..len (returns 0 for an empty range)
+pub fn index(rng: &mut impl Rng, len: usize) -> usize {
+    if len == 0 {
+        return 0;
+    }
+    #[cfg(target_os = "macos")]
+    let value = rng.gen_range(0..len);
+    #[cfg(not(target_os = "macos"))]
+    let value = rng.gen_range(0, len);
+    value
+}
+
+// Streams forked from a run's seed, so one part drawing more numbers never
+// shifts what another draws. Ring colors use the field's own generator.
+pub const RAIN_STREAM: u64 = 1;
+pub const ATTRACT_STREAM: u64 = 2;
+pub const BACKGROUND_STREAM: u64 = 3;
+
+// Seed for stream `stream` of `seed`, well mixed (SplitMix64 finalizer)
+pub fn derive(seed: u64, stream: u64) -> u64 {
+    let mut z = seed.wrapping_add(stream.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+// Generator for stream `stream` of `seed`
+pub fn fork(seed: u64, stream: u64) -> SimRng {
+    seeded(derive(seed, stream))
+}
+
+// Seed for the background sparkles of frame `frame` of a run
+pub fn frame_seed(seed: u64, frame: u64) -> u64 {
+    derive(derive(seed, BACKGROUND_STREAM), frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draws(mut rng: SimRng) -> Vec<usize> {
+        (0..16).map(|_| index(&mut rng, 1000)).collect()
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_sequence() {
+        assert_eq!(draws(seeded(9)), draws(seeded(9)));
+        assert_ne!(draws(seeded(9)), draws(seeded(10)));
+    }
+
+    #[test]
+    fn streams_are_independent_of_each_other() {
+        assert_eq!(draws(fork(9, RAIN_STREAM)), draws(fork(9, RAIN_STREAM)));
+        assert_ne!(draws(fork(9, RAIN_STREAM)), draws(fork(9, ATTRACT_STREAM)));
+        assert_ne!(draws(fork(9, RAIN_STREAM)), draws(seeded(9)));
+        assert_ne!(frame_seed(9, 0), frame_seed(9, 1));
+        assert_eq!(frame_seed(9, 3), frame_seed(9, 3));
+    }
+
+    #[test]
+    fn ranges_stay_in_bounds() {
+        let mut rng = seeded(1);
+        for _ in 0..1000 {
+            let value = range(&mut rng, -2.0, 3.0);
+            assert!((-2.0..3.0).contains(&value));
+            assert!(index(&mut rng, 7) < 7);
+        }
+        assert_eq!(range(&mut rng, 4.0, 4.0), 4.0);
+        assert_eq!(range(&mut rng, 5.0, 1.0), 5.0);
+        assert_eq!(index(&mut rng, 0), 0);
+    }
+}
@@ -0,0 +1,195 @@
+use std::path::Path;
+
+use tiny_skia::{Color, FillRule, LineCap, LineJoin, Paint, PathBuilder, Pixmap, Rect, Stroke, Transform};
+
+use crate::geom::{Bounds, Point};
+use crate::scene::{self, Primitive, Rgba};
+
+// CPU render target: draws scene primitives into an RGBA image with
+// tiny-skia, so frames can be rendered without a GPU or a window. The image
+// covers a `width` x `height` area centered on the simulation origin.
+pub struct Canvas {
+    pixmap: Pixmap,
+    transform: Transform,                   // Simulation coordinates (y up) to pixels (y down)
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32) -> Result<Self, String> {
+        let pixmap = Pixmap::new(width, height).ok_or_else(|| format!("cannot make a {}x{} image", width, height))?;
+        let bounds = Bounds::from_size(width as f32, height as f32);
+        let transform = Transform::from_row(1.0, 0.0, 0.0, -1.0, -bounds.left, bounds.top);
+        Ok(Self { pixmap, transform })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.pixmap.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.pixmap.height()
+    }
+
+    // Area of the simulation the image shows
+    pub fn bounds(&self) -> Bounds {
+        Bounds::from_size(self.width() as f32, self.height() as f32)
+    }
+
+    // Fill the whole image with one color, replacing what was there
+    pub fn clear(&mut self, color: Rgba) {
+        self.pixmap.fill(to_color(color));
+    }
+
+    // Draw primitives in order, each blended over what is already there
+    pub fn draw(&mut self, primitives: &[Primitive]) {
+        for primitive in primitives {
+            match primitive {
+                Primitive::Rect { center, width, height, color } => {
+                    if let Some(rect) = Rect::from_xywh(center.x - width / 2.0, center.y - height / 2.0, *width, *height) {
+                        self.pixmap.fill_rect(rect, &paint(*color), self.transform, None);
+                    }
+                }
+                Primitive::Ellipse { center, width, height, color } => {
+                    if let Some(path) = oval(*center, *width, *height) {
+                        self.pixmap.fill_path(&path, &paint(*color), FillRule::Winding, self.transform, None);
+                    }
+                }
+                Primitive::Ring { center, width, height, weight, color } => {
+                    if let Some(path) = oval(*center, *width, *height) {
+                        self.pixmap.stroke_path(&path, &paint(*color), &stroke(*weight, LineCap::Butt), self.transform, None);
+                    }
+                }
+                Primitive::Line { start, end, weight, color } => self.stroke_points(&[*start, *end], *color, &stroke(*weight, LineCap::Butt), false),
+                Primitive::Polyline { points, weight } => {
+                    // Paths have one paint, so stroke runs of one color. They meet
+                    // on straight stretches, where butt caps fit without overlap.
+                    for run in scene::color_runs(points) {
+                        self.stroke_points(&run.points, run.color, &stroke(*weight, LineCap::Butt), run.closed);
+                    }
+                }
+            }
+        }
+    }
+
+    fn stroke_points(&mut self, points: &[Point], color: Rgba, stroke: &Stroke, closed: bool) {
+        if color[3] <= 0.0 || points.len() < 2 {
+            return;
+        }
+        let mut builder = PathBuilder::new();
+        builder.move_to(points[0].x, points[0].y);
+        for point in &points[1..] {
+            builder.line_to(point.x, point.y);
+        }
+        if closed {
+            builder.close();
+        }
+        if let Some(path) = builder.finish() {
+            self.pixmap.stroke_path(&path, &paint(color), stroke, self.transform, None);
+        }
+    }
+
+    // Pixels as RGBA bytes with straight alpha, rows from the top
+    pub fn to_rgba(&self) -> Vec<u8> {
+        self.pixmap.pixels().iter().flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        }).collect()
+    }
+
+    // The image as PNG file contents
+    pub fn encode_png(&self) -> Result<Vec<u8>, String> {
+        self.pixmap.encode_png().map_err(|e| e.to_string())
+    }
+
+    pub fn save_png(&self, path: &Path) -> Result<(), String> {
+        self.pixmap.save_png(path).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+fn to_color(color: Rgba) -> Color {
+    let [red, green, blue, alpha] = color.map(|component| component.clamp(0.0, 1.0));
+    Color::from_rgba(red, green, blue, alpha).unwrap_or(Color::BLACK)
+}
+
+fn paint(color: Rgba) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color(to_color(color));
+    paint.anti_alias = true;
+    paint
+}
+
+fn stroke(weight: f32, line_cap: LineCap) -> Stroke {
+    Stroke { width: weight.max(0.0), line_cap, line_join: LineJoin::Round, ..Stroke::default() }
+}
+
+fn oval(center: Point, width: f32, height: f32) -> Option<tiny_skia::Path> {
+    let rect = Rect::from_xywh(center.x - width / 2.0, center.y - height / 2.0, width, height)?;
+    PathBuilder::from_oval(rect)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Rgba = [1.0, 0.0, 0.0, 1.0];
+    const BLUE: Rgba = [0.0, 0.0, 1.0, 1.0];
+
+    // Alpha of the pixel covering simulation point (x, y)
+    fn alpha_at(canvas: &Canvas, x: f32, y: f32) -> u8 {
+        let bounds = canvas.bounds();
+        let (column, row) = ((x - bounds.left) as usize, (bounds.top - y) as usize);
+        canvas.to_rgba()[(row * canvas.width() as usize + column) * 4 + 3]
+    }
+
+    // A square outline through the corners, starting and ending at (20, 20)
+    fn square(colors: [Rgba; 4]) -> Primitive {
+        let corners = [(20.0, 20.0), (20.0, -20.0), (-20.0, -20.0), (-20.0, 20.0), (20.0, 20.0)];
+        let points = corners.iter().enumerate().map(|(i, &(x, y))| (Point::new(x, y), colors[i % 4])).collect();
+        Primitive::Polyline { points, weight: 20.0 }
+    }
+
+    #[test]
+    fn draws_into_the_pixel_grid_with_y_up() {
+        let mut canvas = Canvas::new(100, 100).unwrap();
+        canvas.draw(&[Primitive::Rect { center: Point::new(25.0, 25.0), width: 50.0, height: 50.0, color: RED }]);
+        let rgba = canvas.to_rgba();
+        assert_eq!(&rgba[(10 * 100 + 90) * 4..][..4], &[255, 0, 0, 255]);
+        assert_eq!(&rgba[(90 * 100 + 10) * 4..][..4], &[0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn color_changes_leave_no_seam_at_corners() {
+        let mut canvas = Canvas::new(100, 100).unwrap();
+        canvas.draw(&[square([RED, BLUE, RED, BLUE])]);
+        // Just outside the corner where the last run meets the first, and
+        // where the first run ends and the second starts
+        assert_eq!(alpha_at(&canvas, 26.5, 26.5), 255);
+        assert_eq!(alpha_at(&canvas, 26.5, -26.5), 255);
+        assert_eq!(alpha_at(&canvas, 0.0, 0.0), 0);
+    }
+
+    #[test]
+    fn translucent_color_changes_keep_one_alpha() {
+        let mut canvas = Canvas::new(100, 100).unwrap();
+        let [red, blue] = [RED, BLUE].map(|[r, g, b, _]| [r, g, b, 0.5]);
+        canvas.draw(&[square([red, blue, blue, red])]);
+        // Along the middle of every edge, through the runs' ends and the corners
+        let mut alphas = Vec::new();
+        for step in 0..40 {
+            let along = step as f32 - 19.5;
+            for (x, y) in [(20.5, along), (-20.5, along), (along, 20.5), (along, -20.5)] {
+                alphas.push(alpha_at(&canvas, x, y));
+            }
+        }
+        alphas.extend([alpha_at(&canvas, 26.5, 26.5), alpha_at(&canvas, -26.5, -26.5)]);
+        assert!(alphas.iter().all(|&alpha| alpha == alphas[0]), "{:?}", alphas);
+        assert!(alphas[0].abs_diff(128) <= 1);
+    }
+
+    #[test]
+    fn one_color_rings_close_where_they_start() {
+        let mut canvas = Canvas::new(100, 100).unwrap();
+        canvas.draw(&[square([RED; 4])]);
+        assert_eq!(alpha_at(&canvas, 26.5, 26.5), 255);
+        assert_eq!(alpha_at(&canvas, -26.5, -26.5), 255);
+    }
+}
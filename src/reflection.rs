@@ -0,0 +1,124 @@
+use crate::geom::{Bounds, Point};
+
+// Which window edges act as reflecting walls
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Edges {
+    pub left: bool,
+    pub right: bool,
+    pub bottom: bool,
+    pub top: bool,
+}
+
+impl Edges {
+    pub const ALL: Edges = Edges { left: true, right: true, bottom: true, top: true };
+    pub const NONE: Edges = Edges { left: false, right: false, bottom: false, top: false };
+    pub const SHORE: Edges = Edges { left: false, right: false, bottom: true, top: false };
+}
+
+impl Default for Edges {
+    fn default() -> Self {
+        Edges::ALL
+    }
+}
+
+// Reflection of the plane across a vertical and/or horizontal wall.
+// An image source is the ripple's center mapped through one of these.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mirror {
+    pub axis_x: Option<f32>,                // x of a vertical wall to flip across
+    pub axis_y: Option<f32>,                // y of a horizontal wall to flip across
+}
+
+impl Mirror {
+    pub const IDENTITY: Mirror = Mirror { axis_x: None, axis_y: None };
+
+    // Map a point into the mirrored image
+    pub fn apply(&self, point: Point) -> Point {
+        Point::new(
+            self.axis_x.map_or(point.x, |axis| 2.0 * axis - point.x),
+            self.axis_y.map_or(point.y, |axis| 2.0 * axis - point.y),
+        )
+    }
+}
+
+// Distance from a point to the nearest point of a rectangle (zero inside)
+fn distance_to_bounds(point: Point, bounds: Bounds) -> f32 {
+    let dx = (bounds.left - point.x).max(point.x - bounds.right).max(0.0);
+    let dy = (bounds.bottom - point.y).max(point.y - bounds.top).max(0.0);
+    (dx * dx + dy * dy).sqrt()
+}
+
+// Image sources for a wave centered at `center` inside `bounds`: one per
+// reflecting edge, plus a corner image wherever two adjacent edges both
+// reflect. Images that are still too far away for a wave of `radius` to
+// reach the window are skipped.
+pub fn image_sources(center: Point, radius: f32, bounds: Bounds, edges: Edges) -> Vec<Mirror> {
+    if !bounds.contains(center) {
+        return Vec::new();
+    }
+
+    let mut walls_x = vec![None];
+    if edges.left { walls_x.push(Some(bounds.left)); }
+    if edges.right { walls_x.push(Some(bounds.right)); }
+    let mut walls_y = vec![None];
+    if edges.bottom { walls_y.push(Some(bounds.bottom)); }
+    if edges.top { walls_y.push(Some(bounds.top)); }
+
+    let mut mirrors = Vec::new();
+    for &axis_x in &walls_x {
+        for &axis_y in &walls_y {
+            let mirror = Mirror { axis_x, axis_y };
+            if mirror == Mirror::IDENTITY {
+                continue;
+            }
+            if distance_to_bounds(mirror.apply(center), bounds) < radius {
+                mirrors.push(mirror);
+            }
+        }
+    }
+    mirrors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOUNDS: Bounds = Bounds::new(-100.0, 100.0, -100.0, 100.0);
+    const CENTER: Point = Point::new(80.0, 20.0);
+
+    fn images(radius: f32, edges: Edges) -> Vec<Point> {
+        image_sources(CENTER, radius, BOUNDS, edges).iter().map(|mirror| mirror.apply(CENTER)).collect()
+    }
+
+    #[test]
+    fn mirrors_the_center_across_each_edge() {
+        let all = images(1000.0, Edges::ALL);
+        for image in [Point::new(-280.0, 20.0), Point::new(120.0, 20.0), Point::new(80.0, -220.0), Point::new(80.0, 180.0)] {
+            assert!(all.contains(&image), "{:?} missing from {:?}", image, all);
+        }
+        // Corner images flip across both walls
+        assert!(all.contains(&Point::new(120.0, 180.0)));
+        assert!(all.contains(&Point::new(-280.0, -220.0)));
+        assert_eq!(all.len(), 8);
+    }
+
+    #[test]
+    fn skips_images_the_wave_cannot_reach_yet() {
+        assert!(images(15.0, Edges::ALL).is_empty());
+        assert_eq!(images(50.0, Edges::ALL), [Point::new(120.0, 20.0)]);
+        assert_eq!(images(1000.0, Edges::SHORE), [Point::new(80.0, -220.0)]);
+        assert!(images(1000.0, Edges::NONE).is_empty());
+    }
+
+    #[test]
+    fn waves_outside_the_window_are_not_reflected() {
+        assert!(image_sources(Point::new(150.0, 0.0), 1000.0, BOUNDS, Edges::ALL).is_empty());
+    }
+
+    #[test]
+    fn mirroring_twice_is_the_identity() {
+        let mirror = Mirror { axis_x: Some(100.0), axis_y: Some(-100.0) };
+        assert_eq!(mirror.apply(mirror.apply(CENTER)), CENTER);
+        assert_eq!(Mirror::IDENTITY.apply(CENTER), CENTER);
+    }
+}
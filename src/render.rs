@@ -0,0 +1,140 @@
+// Headless rendering: `waves render` steps the simulation at a fixed frame
+// rate, driven by a recorded session or a timeline, and writes the frames
+// as a PNG sequence, a GIF or an APNG, or the last one as an SVG. Nothing
+// depends on the wall clock, so the same arguments always produce the same
+// frames.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cli::RenderArgs;
+use waves::{animation, palette, random, session, timeline, ApngWriter, BackgroundType, GifWriter, Palette, Renderer, Replay, RippleField, SceneSettings, Script, Seamless};
+
+// Set up the renderer `args` describe: the script, its seed and palettes,
+// and the scene as the window starts out
+fn renderer(args: &RenderArgs) -> Result<Renderer, String> {
+    let script = match (&args.replay, &args.timeline) {
+        (Some(path), None) => {
+            let mut replay = Replay::new(session::load(path)?);
+            replay.speed = args.replay_speed;
+            replay.looped = args.loop_replay;
+            Script::Replay(replay)
+        }
+        (None, Some(path)) => Script::Timeline(timeline::load(path)?),
+        _ => return Err("render needs either --replay or --timeline".to_string()),
+    };
+    let seed = args.seed.or(script.seed()).unwrap_or_else(random::random_seed);
+
+    let mut scene = SceneSettings::new(crate::DEFAULT_WOBBLE, crate::DEFAULT_FADE);
+    if let Some(name) = &args.background {
+        scene.background_type = BackgroundType::from_name(name).ok_or_else(|| format!("unknown background '{}'", name))?;
+    }
+    let mut field = RippleField::with_seed(seed);
+    field.timing = crate::DEFAULT_TIMING;
+    if let Some(dir) = palette::config_dir() {
+        for error in field.palettes.load_dir(&dir) {
+            eprintln!("Could not load palette: {}", error);
+        }
+    }
+    if let Some(name) = &args.palette && !field.palettes.select(name) {
+        return Err(format!("unknown palette '{}'", name));
+    }
+
+    let frames = args.frame_count() + args.seamless_frames();
+    Renderer::new(script, field, scene, args.width, args.height, args.fps, frames)
+}
+
+// Print what the renderer ran into since the last report
+fn report(renderer: &mut Renderer) {
+    for warning in renderer.take_warnings() {
+        eprintln!("Warning: {}", warning);
+    }
+}
+
+// Where rendered frames go, picked by the extension of `--out`
+enum Output {
+    Sequence(PathBuf),                  // Numbered PNG files in a directory
+    Gif(GifWriter),
+    Apng(ApngWriter),
+}
+
+impl Output {
+    fn create(args: &RenderArgs, palette: &Palette, frames: u32) -> Result<Self, String> {
+        let path = &args.output;
+        let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or("").to_ascii_lowercase();
+        if let Some(dir) = path.parent().filter(|dir| !extension.is_empty() && !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+        match extension.as_str() {
+            "gif" => GifWriter::create(path, args.width, args.height, args.fps, args.quantization, palette, args.dither).map(Output::Gif),
+            "png" | "apng" => ApngWriter::create(path, args.width, args.height, args.fps, frames).map(Output::Apng),
+            _ => {
+                fs::create_dir_all(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+                Ok(Output::Sequence(path.clone()))
+            }
+        }
+    }
+
+    fn write(&mut self, frame: u32, width: u32, height: u32, rgba: &[u8]) -> Result<(), String> {
+        match self {
+            Output::Sequence(dir) => animation::save_png(&frame_path(dir, frame), width, height, rgba),
+            Output::Gif(writer) => writer.write(rgba),
+            Output::Apng(writer) => writer.write(rgba),
+        }
+    }
+
+    fn finish(self) -> Result<(), String> {
+        match self {
+            Output::Sequence(_) => Ok(()),
+            Output::Gif(writer) => writer.finish(),
+            Output::Apng(writer) => writer.finish(),
+        }
+    }
+}
+
+fn frame_path(dir: &Path, frame: u32) -> PathBuf {
+    dir.join(format!("frame_{:05}.png", frame))
+}
+
+// Render the frames described by `args`. With `--seamless`, the extra
+// frames past the end are crossfaded into the start so the output loops.
+pub fn run(args: &RenderArgs) -> Result<(), String> {
+    let mut renderer = renderer(args)?;
+    report(&mut renderer);
+    if args.output.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("svg")) {
+        return save_svg(&mut renderer, args);
+    }
+    let mut seamless = Seamless::new(renderer.frame_count() as usize, args.seamless_frames() as usize);
+    let frames = seamless.output_len() as u32;
+    let mut output = Output::create(args, renderer.palette(), frames)?;
+    let mut frame = 0;
+    while let Some(canvas) = renderer.render_next() {
+        let rgba = seamless.push(&canvas.to_rgba());
+        report(&mut renderer);
+        let Some(rgba) = rgba else {
+            continue;
+        };
+        output.write(frame, args.width, args.height, &rgba)?;
+        frame += 1;
+        if frame % args.fps == 0 || frame == frames {
+            println!("Rendered {}/{} frames", frame, frames);
+        }
+    }
+    output.finish()?;
+    // With the seed a random run can be rendered again
+    println!("Saved {} (seed {})", args.output.display(), renderer.seed());
+    Ok(())
+}
+
+// Run the simulation through `--duration` and save where it ends up as an SVG
+fn save_svg(renderer: &mut Renderer, args: &RenderArgs) -> Result<(), String> {
+    while renderer.render_next().is_some() {
+        report(renderer);
+    }
+    if let Some(dir) = args.output.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    renderer.svg(args.svg_background)?.save(&args.output)?;
+    println!("Saved {} (seed {})", args.output.display(), renderer.seed());
+    Ok(())
+}
@@ -0,0 +1,316 @@
+// Frame-by-frame rendering of a scripted run without a window. A recorded
+// session or a timeline drives the field, and every frame is drawn into a
+// CPU canvas the way the window would draw it. Time advances by whole
+// frames, never by the wall clock, so a run always renders the same.
+
+use noise::Perlin;
+
+use crate::background::{self, BackgroundType};
+use crate::field::RippleField;
+use crate::geom::Point;
+use crate::obstacle;
+use crate::palette::Palette;
+use crate::random;
+use crate::raster::Canvas;
+use crate::scene;
+use crate::session::{Action, Replay};
+use crate::settings::{MenuButton, SceneSettings};
+use crate::shape::RippleShape;
+use crate::svg::{BackgroundStyle, SvgDocument};
+use crate::timeline::{SceneState, Timeline};
+
+// Whatever makes ripples happen during a render
+pub enum Script {
+    Replay(Replay),
+    Timeline(Timeline),
+}
+
+impl Script {
+    // Seed a replayed session was recorded with
+    pub fn seed(&self) -> Option<u64> {
+        match self {
+            Script::Replay(replay) => replay.seed(),
+            Script::Timeline(_) => None,
+        }
+    }
+}
+
+// The simulation and canvas of a render in progress
+pub struct Renderer {
+    script: Script,
+    field: RippleField,
+    noise: Perlin,
+    scene: SceneSettings,
+    replay_start: Option<(RippleField, SceneSettings)>, // Field and scene as the first pass of a session began
+    shapes: Vec<RippleShape>,
+    canvas: Canvas,
+    fps: u32,
+    frame: u32,                         // Frames rendered so far
+    frames: u32,                        // Frames to render in total
+    time: f32,                          // Simulation time of the latest frame
+    frame_seed: u64,                    // Seeded the latest frame's background sparkles
+    run_frame: u64,                     // Frames since the run (re)started, numbers the sparkle seeds
+    warnings: Vec<String>,              // Problems not yet reported by the front end
+}
+
+impl Renderer {
+    // Render `frames` frames of `width` x `height` pixels at `fps`. The field
+    // brings the seed, palettes and timing, `scene` how frames look at the
+    // start. Sessions made only of wave impulses are refused, since renders
+    // show ring mode.
+    pub fn new(script: Script, field: RippleField, scene: SceneSettings, width: u32, height: u32, fps: u32, frames: u32) -> Result<Self, String> {
+        let mut warnings = Vec::new();
+        if let Script::Replay(replay) = &script {
+            let impulses = replay.events.iter().filter(|event| matches!(event.action, Action::Impulse { .. })).count();
+            let spawns = replay.events.iter().filter(|event| matches!(event.action, Action::Spawn { .. })).count();
+            if impulses > 0 && spawns == 0 {
+                return Err("the session only has wave impulses, and renders show ring mode".to_string());
+            }
+            if impulses > 0 {
+                warnings.push(format!("leaving out wave impulses ({}), renders show ring mode", impulses));
+            }
+        }
+        Ok(Self {
+            script,
+            noise: random::seeded_noise(field.seed()),
+            field,
+            scene,
+            replay_start: None,
+            shapes: RippleShape::PRESETS.to_vec(),
+            canvas: Canvas::new(width, height)?,
+            fps,
+            frame: 0,
+            frames,
+            time: 0.0,
+            frame_seed: 0,
+            run_frame: 0,
+            warnings,
+        })
+    }
+
+    // Seed of the run, recorded or chosen at random
+    pub fn seed(&self) -> u64 {
+        self.field.seed()
+    }
+
+    // Frames rendered in total
+    pub fn frame_count(&self) -> u32 {
+        self.frames
+    }
+
+    // Ring palette in use
+    pub fn palette(&self) -> &Palette {
+        self.field.palettes.active()
+    }
+
+    // Problems met since the last call, such as unknown names in the script
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    // Advance one frame and draw it, or None once every frame is done
+    pub fn render_next(&mut self) -> Option<&Canvas> {
+        if self.frame >= self.frames {
+            return None;
+        }
+        let dt = 1.0 / self.fps as f32;
+        self.time = self.frame as f32 * dt;
+        // The first frame shows the start of the run, later ones a frame apart
+        let step = if self.frame == 0 { 0.0 } else { dt };
+
+        self.field.update(step);
+        self.frame_seed = random::frame_seed(self.field.seed(), self.run_frame);
+        self.run_frame += 1;
+        self.run_script(step);
+        self.draw(step);
+
+        self.frame += 1;
+        Some(&self.canvas)
+    }
+
+    // Apply the session actions or timeline cues due by now
+    fn run_script(&mut self, step: f32) {
+        let bounds = self.canvas.bounds();
+        let actions = match &mut self.script {
+            Script::Replay(replay) => replay.update(step),
+            Script::Timeline(timeline) => {
+                for cue in timeline.update(self.time) {
+                    if let Err(error) = cue.spawn(&mut self.field, bounds) {
+                        self.warnings.push(format!("skipping timeline ripple at {}s: {}", cue.time, error));
+                    }
+                    self.apply(&cue.scene());
+                }
+                return;
+            }
+        };
+        for action in actions {
+            match action {
+                // Menu buttons toggle settings, so every pass goes back to the
+                // settings the first one started from. The session places its
+                // own obstacles, so every pass starts without any.
+                Action::Start { .. } => {
+                    match &self.replay_start {
+                        Some((field, scene)) => {
+                            self.field = field.clone();
+                            self.scene = *scene;
+                        }
+                        None => self.replay_start = Some((self.field.clone(), self.scene)),
+                    }
+                    self.restart();
+                    self.field.obstacles.clear();
+                }
+                Action::Spawn { .. } => {
+                    if let Some(spawn) = action.to_spawn() {
+                        self.field.emit(&spawn);
+                    }
+                }
+                // Counted when the render started
+                Action::Impulse { .. } => {}
+                Action::Menu { button } => match MenuButton::from_name(&button) {
+                    Some(MenuButton::Seed) => self.restart(),
+                    // Buttons that do not change the look, such as rain, do nothing here
+                    Some(button) => {
+                        self.scene.press(&mut self.field, &self.shapes, button);
+                    }
+                    None => self.warnings.push(format!("ignoring unknown menu button '{}' in session", button)),
+                },
+                // Raindrops were recorded as the ripples they spawned
+                Action::Rain { .. } => {}
+                Action::AddObstacle { obstacle } => match obstacle::parse_line(&obstacle) {
+                    Ok(obstacle) => self.field.obstacles.push(obstacle),
+                    Err(error) => self.warnings.push(format!("ignoring obstacle '{}' in session: {}", obstacle, error)),
+                },
+                Action::MoveObstacle { index, x, y } => {
+                    if let Some(obstacle) = self.field.obstacles.get_mut(index) {
+                        obstacle.move_to(Point::new(x, y));
+                    }
+                }
+                Action::DeleteObstacle { index } => {
+                    if index < self.field.obstacles.len() {
+                        self.field.obstacles.remove(index);
+                    }
+                }
+            }
+        }
+    }
+
+    // Switch background, wobble and fade as a timeline cue asks
+    fn apply(&mut self, state: &SceneState) {
+        if let Some(name) = &state.background {
+            match BackgroundType::from_name(name) {
+                Some(background) => self.scene.background_type = background,
+                None => self.warnings.push(format!("ignoring unknown background '{}' in timeline", name)),
+            }
+        }
+        if let Some(wobble) = state.wobble {
+            self.scene.wobble_enabled = wobble;
+        }
+        if let Some(fade) = state.fade {
+            self.scene.fade_enabled = fade;
+        }
+    }
+
+    // Start over from the run's seed, as the Seed button does on screen
+    fn restart(&mut self) {
+        let seed = self.field.seed();
+        self.field.reseed(seed);
+        self.field.clear();
+        self.noise = random::seeded_noise(seed);
+        self.run_frame = 0;
+    }
+
+    // The latest frame as an SVG document
+    pub fn svg(&self, background_style: BackgroundStyle) -> Result<SvgDocument, String> {
+        let bounds = self.canvas.bounds();
+        let mut backdrop = Vec::new();
+        let mut rng = random::seeded(self.frame_seed);
+        background::draw(&mut backdrop, self.scene.background_type, bounds, &self.noise, self.time, 1.0, &mut rng);
+        let style = self.scene.ring_style(self.time, self.noise);
+        SvgDocument::frame(&self.field, &style, bounds, self.scene.reflect(), &backdrop, background_style)
+    }
+
+    // Draw the current state the way the window would
+    fn draw(&mut self, step: f32) {
+        let (time, bounds) = (self.time, self.canvas.bounds());
+        let mut primitives = Vec::new();
+        let alpha = if self.scene.trails_enabled && self.frame > 0 {
+            let alpha = 1.0 - (-step / self.scene.trail_persistence).exp();
+            scene::fade(&mut primitives, bounds, alpha);
+            alpha
+        } else {
+            self.canvas.clear([0.0, 0.0, 0.0, 1.0]);
+            1.0
+        };
+        let mut rng = random::seeded(self.frame_seed);
+        background::draw(&mut primitives, self.scene.background_type, bounds, &self.noise, time, alpha, &mut rng);
+        let style = self.scene.ring_style(time, self.noise);
+        scene::ripples(&mut primitives, &self.field, &style, bounds, self.scene.reflect());
+        self.canvas.draw(&primitives);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ripple::RippleKind;
+    use crate::session::Event;
+
+    fn event(time: f32, action: Action) -> Event {
+        Event { time, action }
+    }
+
+    fn spawn(time: f32) -> Event {
+        let (x, y, kind, strength, stretch, direction) = (0.0, 0.0, RippleKind::Drop, 1.0, 1.0, 0.0);
+        event(time, Action::Spawn { x, y, kind, strength, stretch, direction })
+    }
+
+    fn menu(time: f32, button: MenuButton) -> Event {
+        event(time, Action::Menu { button: button.name().to_string() })
+    }
+
+    // Every frame of a short session with wobbling, fading rings over water
+    fn frames(seed: u64) -> Vec<Vec<u8>> {
+        let events = vec![event(0.0, Action::Start { seed }), spawn(0.0), spawn(0.3), menu(0.5, MenuButton::Colors), spawn(0.6)];
+        let scene = SceneSettings::new(true, true);
+        let mut renderer = Renderer::new(Script::Replay(Replay::new(events)), RippleField::with_seed(seed), scene, 48, 48, 10, 12).unwrap();
+        let mut frames = Vec::new();
+        while let Some(canvas) = renderer.render_next() {
+            frames.push(canvas.to_rgba());
+        }
+        frames
+    }
+
+    #[test]
+    fn the_same_seed_renders_the_same_frames() {
+        let first = frames(7);
+        assert_eq!(first.len(), 12);
+        assert_eq!(first, frames(7));
+        assert_ne!(first, frames(8));
+    }
+
+    #[test]
+    fn looped_sessions_start_every_pass_from_the_same_settings() {
+        let events = vec![
+            event(0.0, Action::Start { seed: 1 }),
+            spawn(0.1),
+            menu(0.45, MenuButton::Wobble),
+            menu(0.45, MenuButton::Palette),
+            spawn(1.05),
+        ];
+        let mut replay = Replay::new(events);
+        replay.looped = true;
+        let scene = SceneSettings::new(true, true);
+        let mut renderer = Renderer::new(Script::Replay(replay), RippleField::with_seed(1), scene, 32, 32, 10, 60).unwrap();
+        let mut states = Vec::new();
+        while renderer.render_next().is_some() {
+            let state = (renderer.scene, renderer.palette().name.clone());
+            if !states.contains(&state) {
+                states.push(state);
+            }
+        }
+        // Toggled and back again, rather than drifting further every pass
+        assert_eq!(states.len(), 2, "{:?}", states);
+        assert_eq!(states[0].0, scene);
+        assert!(!states[1].0.wobble_enabled);
+    }
+}
@@ -0,0 +1,296 @@
+use noise::Perlin;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::color::{self, Color, Interpolation};
+use crate::easing::Easing;
+use crate::geom::{Bounds, Point};
+use crate::rainbow::Rainbow;
+use crate::random;
+use crate::reflection::{image_sources, Edges, Mirror};
+use crate::shape::RippleShape;
+use crate::wobble::{Wobble, NOISE_OFFSET_RANGE};
+
+// Default ripple parameters (see `RippleParams`)
+pub const AMOUNT: i16 = 5;                  // Maximum number of rings per ripple
+pub const MAX_RADIUS: f32 = 200.0;          // Maximum radius before ripple disappears
+pub const RADIUS_SPEED: f32 = 90.0;         // How fast ripples grow, in pixels per second
+pub const INITIAL_RADIUS: f32 = 10.0;       // Starting radius for ripples
+pub const COLOR_CHANGE_FACTOR: f32 = 7.0;   // Spacing between rings
+pub const STROKE_WEIGHT: f32 = 3.0;         // Line thickness for ripples
+pub const FADE_DISTANCE: f32 = 50.0;        // Distance over which ripples fade out
+pub const RADIUS_EASING: Easing = Easing::Linear;   // Radius over lifetime
+pub const ALPHA_EASING: Easing = Easing::Linear;    // Fade over the last FADE_DISTANCE pixels
+
+// Wobble shape configuration
+pub const WOBBLE_AMOUNT: f32 = 0.8;         // How much rings wobble
+pub const WOBBLE_SPEED: f32 = 2.0;          // Speed of wobble animation
+
+// Size, speed and look of a single ripple
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RippleParams {
+    pub amount: i16,                        // Maximum number of rings
+    pub max_radius: f32,                    // Radius at which the ripple disappears
+    pub radius_speed: f32,                  // Growth in pixels per second
+    pub initial_radius: f32,                // Starting radius
+    pub ring_spacing: f32,                  // Distance between concentric rings
+    pub stroke_weight: f32,                 // Line thickness
+    pub fade_distance: f32,                 // Distance over which the ripple fades out
+    pub radius_easing: Easing,              // Shape of the radius over the ripple's lifetime
+    pub alpha_easing: Easing,               // Shape of the fade out
+    pub stretch: f32,                       // Elongation along `direction` (1 = circle)
+    pub direction: f32,                     // Direction of elongation in radians
+}
+
+impl RippleParams {
+    pub const DEFAULT: RippleParams = RippleParams {
+        amount: AMOUNT,
+        max_radius: MAX_RADIUS,
+        radius_speed: RADIUS_SPEED,
+        initial_radius: INITIAL_RADIUS,
+        ring_spacing: COLOR_CHANGE_FACTOR,
+        stroke_weight: STROKE_WEIGHT,
+        fade_distance: FADE_DISTANCE,
+        radius_easing: RADIUS_EASING,
+        alpha_easing: ALPHA_EASING,
+        stretch: 1.0,
+        direction: 0.0,
+    };
+
+    // Seconds from spawn until the ripple reaches `max_radius`, assuming
+    // `radius_speed` is its average growth rate
+    pub fn lifetime(&self) -> f32 {
+        (self.max_radius - self.initial_radius).max(0.0) / self.radius_speed.max(f32::EPSILON)
+    }
+}
+
+impl Default for RippleParams {
+    fn default() -> Self {
+        RippleParams::DEFAULT
+    }
+}
+
+// Named ripple presets, so different input sources can spawn different ripples
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RippleKind {
+    Drop,                                   // Standard ripple (left click)
+    Swell,                                  // Big, slow ripple (right click)
+    Droplet,                                // Small, fast ripple (scripted events)
+}
+
+impl RippleKind {
+    pub const ALL: [RippleKind; 3] = [RippleKind::Drop, RippleKind::Swell, RippleKind::Droplet];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RippleKind::Drop => "drop",
+            RippleKind::Swell => "swell",
+            RippleKind::Droplet => "droplet",
+        }
+    }
+
+    // Parameters a ripple of this kind starts with
+    pub fn default_params(self) -> RippleParams {
+        match self {
+            RippleKind::Drop => RippleParams::DEFAULT,
+            RippleKind::Swell => RippleParams {
+                amount: 8,
+                max_radius: 360.0,
+                radius_speed: 55.0,
+                initial_radius: 16.0,
+                ring_spacing: 12.0,
+                stroke_weight: 4.0,
+                fade_distance: 120.0,
+                ..RippleParams::DEFAULT
+            },
+            RippleKind::Droplet => RippleParams {
+                amount: 3,
+                max_radius: 90.0,
+                radius_speed: 150.0,
+                initial_radius: 4.0,
+                ring_spacing: 5.0,
+                stroke_weight: 2.0,
+                fade_distance: 30.0,
+                ..RippleParams::DEFAULT
+            },
+        }
+    }
+}
+
+// Where ring colors come from
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Coloring {
+    #[default]
+    Fixed,                                  // One palette color per ring
+    Lifetime(Interpolation),                // Palette colors blended as each ring grows
+    Rainbow(Rainbow),                       // Hue rotating around each ring
+}
+
+impl Coloring {
+    // Check if colors change along a ring, so it has to be drawn point by point
+    pub fn varies_along_ring(&self) -> bool {
+        matches!(self, Coloring::Rainbow(_))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Ripple {
+    pub center: Point,                      // Center point of the ripple
+    pub color_sequence: Vec<Color>,         // Colors for each concentric ring, picked up front
+    pub radius: f32,                        // Current radius of the outermost ring
+    pub age: f32,                           // Seconds since the ripple was created
+    pub copies: i16,                        // Number of concentric rings
+    pub params: RippleParams,               // Size, speed and look of this ripple
+    pub shape: RippleShape,                 // Outline followed by every ring
+    pub noise_offset: f32,                  // Where this ripple samples the wobble noise
+    pub serial: u64,                        // Spawn order within the field, offsets rainbow hues
+}
+
+impl Ripple {
+    // Create a new ripple at the specified position. `color_sequence` holds
+    // the colors of every ring it will grow (at least one), picked up front
+    // so updates need no randomness.
+    pub fn new(position: Point, params: RippleParams, color_sequence: Vec<Color>, rng: &mut impl Rng) -> Self {
+        Self {
+            center: position,
+            color_sequence,
+            radius: params.initial_radius,
+            age: 0.0,
+            copies: 1,
+            params,
+            shape: RippleShape::Circle,
+            noise_offset: random::range(rng, 0.0, NOISE_OFFSET_RANGE),
+            serial: 0,
+        }
+    }
+
+    // Update ripple state (growth and spawning new rings) over `dt` seconds
+    pub fn update(&mut self, dt: f32) {
+        // Grow the ripple along its radius curve
+        self.age += dt;
+        let span = self.params.max_radius - self.params.initial_radius;
+        self.radius = self.params.initial_radius + span * self.params.radius_easing.apply(self.progress());
+
+        // Add new color rings as the ripple grows. A long step can cross
+        // several ring thresholds at once, so keep adding until caught up.
+        while self.copies < self.params.amount && self.radius > self.copies as f32 * self.params.ring_spacing {
+            self.copies += 1;
+        }
+    }
+
+    // Fraction of the ripple's lifetime that has passed (0 to 1)
+    pub fn progress(&self) -> f32 {
+        (self.age / self.params.lifetime()).min(1.0)
+    }
+
+    // Calculate opacity based on ripple age
+    pub fn opacity(&self, fade_enabled: bool) -> f32 {
+        if fade_enabled {
+            // Without a fade distance the ripple stays opaque until it drops out
+            if self.params.fade_distance <= 0.0 {
+                return if self.radius < self.params.max_radius { 1.0 } else { 0.0 };
+            }
+            // Fade out as the ripple approaches maximum size
+            let fade_start = self.params.max_radius - self.params.fade_distance;
+            if self.radius > fade_start {
+                let fade = (self.radius - fade_start) / self.params.fade_distance;
+                return (1.0 - self.params.alpha_easing.apply(fade)).clamp(0.0, 1.0);
+            }
+        }
+        1.0
+    }
+
+    // Radius of the given ring, counting inwards from the outermost one
+    pub fn ring_radius(&self, ring: i16) -> f32 {
+        self.radius - ring as f32 * self.params.ring_spacing
+    }
+
+    // Color of a ring as sRGB values in 0..1, at `degrees` around it. With
+    // a lifetime gradient, each ring moves through the ripple's colors as it
+    // grows, starting from its own.
+    pub fn ring_color(&self, ring: i16, coloring: &Coloring, degrees: f32, time: f32) -> [f32; 3] {
+        let index = ring as usize % self.color_sequence.len();
+        match coloring {
+            Coloring::Fixed => self.color_sequence[index].to_f32(),
+            Coloring::Lifetime(interpolation) => {
+                let count = self.color_sequence.len();
+                let stops: Vec<Color> = (0..count).map(|k| self.color_sequence[(index + k) % count]).collect();
+                let t = self.ring_radius(ring) / self.params.max_radius.max(f32::EPSILON);
+                color::gradient(&stops, t, *interpolation)
+            }
+            Coloring::Rainbow(rainbow) => rainbow.color(time, self.serial, ring, degrees),
+        }
+    }
+
+    // Check if the ripple is an ellipse rather than a circle
+    pub fn is_stretched(&self) -> bool {
+        self.params.stretch != 1.0
+    }
+
+    // Check if rings can be drawn as plain circles
+    pub fn is_circle(&self) -> bool {
+        self.shape == RippleShape::Circle && !self.is_stretched()
+    }
+
+    // Sample the outline of a ring as a closed polyline, wobbling if a
+    // wobble is given (`noise` drives the noise mode). Stretched ripples are
+    // elongated along their direction of motion.
+    pub fn outline_points(&self, ring: i16, time: f32, wobble: Option<&Wobble>, noise: &Perlin) -> Vec<Point> {
+        let ring_radius = self.ring_radius(ring);
+        let (sin_dir, cos_dir) = self.params.direction.sin_cos();
+        self.shape.unit_outline().into_iter().map(|unit| {
+            // Wobble by direction from the center, so every shape bends the same way
+            let deg = unit.y.atan2(unit.x).to_degrees().rem_euclid(360.0);
+            let displacement = wobble.map_or(0.0, |w| w.displacement(noise, self.noise_offset, ring, deg, time));
+            let wobble_radius = ring_radius * (1.0 + displacement);
+            // Shape in the ripple's own frame, then stretched and rotated onto the motion
+            let along = wobble_radius * unit.x * self.params.stretch;
+            let across = wobble_radius * unit.y;
+            Point::new(
+                self.center.x + along * cos_dir - across * sin_dir,
+                self.center.y + along * sin_dir + across * cos_dir,
+            )
+        }).collect()
+    }
+
+    // Mirrors producing this ripple's visible reflections off the given walls
+    pub fn reflections(&self, bounds: Bounds, edges: Edges) -> Vec<Mirror> {
+        image_sources(self.center, self.radius, bounds, edges)
+    }
+
+    // Check if ripple should be removed
+    pub fn is_expired(&self) -> bool {
+        self.progress() >= 1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ripple(params: RippleParams) -> Ripple {
+        Ripple::new(Point::default(), params, vec![Color::new(255, 255, 255)], &mut random::seeded(1))
+    }
+
+    #[test]
+    fn fades_over_the_last_fade_distance() {
+        let mut ripple = ripple(RippleParams::DEFAULT);
+        ripple.radius = MAX_RADIUS - FADE_DISTANCE;
+        assert_eq!(ripple.opacity(true), 1.0);
+        ripple.radius = MAX_RADIUS - FADE_DISTANCE / 2.0;
+        assert_eq!(ripple.opacity(true), 0.5);
+        assert_eq!(ripple.opacity(false), 1.0);
+    }
+
+    #[test]
+    fn no_fade_distance_drops_out_at_the_maximum_radius() {
+        for fade_distance in [0.0, -5.0] {
+            let mut ripple = ripple(RippleParams { fade_distance, ..RippleParams::DEFAULT });
+            ripple.radius = MAX_RADIUS - 1.0;
+            assert_eq!(ripple.opacity(true), 1.0);
+            ripple.radius = MAX_RADIUS;
+            assert_eq!(ripple.opacity(true), 0.0);
+        }
+    }
+}
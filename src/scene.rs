@@ -0,0 +1,193 @@
+use noise::Perlin;
+
+use crate::field::RippleField;
+use crate::geom::{Bounds, Point};
+use crate::reflection::{Edges, Mirror};
+use crate::ripple::{Coloring, Ripple};
+use crate::splash::Splashes;
+use crate::wobble::Wobble;
+
+// Color with straight (not premultiplied) alpha, components in 0..1
+pub type Rgba = [f32; 4];
+
+// Gap between the ends of a polyline below which it counts as closed
+const CLOSING_DISTANCE: f32 = 0.01;
+
+// A drawing command in simulation coordinates (origin at the window center,
+// y up). Scenes are built as lists of these so the on-screen, raster and SVG
+// backends all draw the same thing.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Primitive {
+    Rect { center: Point, width: f32, height: f32, color: Rgba },     // Filled rectangle
+    Ellipse { center: Point, width: f32, height: f32, color: Rgba },  // Filled ellipse
+    Ring { center: Point, width: f32, height: f32, weight: f32, color: Rgba }, // Ellipse outline
+    Line { start: Point, end: Point, weight: f32, color: Rgba },
+    Polyline { points: Vec<(Point, Rgba)>, weight: f32 },              // Open path, colored per point
+}
+
+// Frame-wide settings for drawing ripple rings
+#[derive(Clone, Copy)]
+pub struct RingStyle {
+    pub time: f32,                          // Application time, animates the wobble and rainbow
+    pub wobble: Option<Wobble>,             // Outline deformation, if enabled
+    pub noise: Perlin,                      // Noise sampled by the noise wobble
+    pub fade_enabled: bool,                 // Fade rings out near their maximum size
+    pub coloring: Coloring,                 // Where ring colors come from
+}
+
+// Cover everything drawn so far with black at `alpha`, so earlier frames
+// fade out instead of being cleared (trails mode)
+pub fn fade(out: &mut Vec<Primitive>, bounds: Bounds, alpha: f32) {
+    out.push(Primitive::Rect {
+        center: bounds.center(),
+        width: bounds.width(),
+        height: bounds.height(),
+        color: [0.0, 0.0, 0.0, alpha],
+    });
+}
+
+// Add every ripple of the field plus its splash droplets. With `reflect`
+// set, rings also bounce off those edges of `bounds`.
+pub fn ripples(out: &mut Vec<Primitive>, field: &RippleField, style: &RingStyle, bounds: Bounds, reflect: Option<Edges>) {
+    for ripple in &field.ripples {
+        // Image sources for reflections off the enabled window edges
+        let reflections = match reflect {
+            Some(edges) => ripple.reflections(bounds, edges),
+            None => Vec::new(),
+        };
+        self::ripple(out, ripple, field, style, &reflections);
+    }
+    particles(out, &field.splashes);
+}
+
+// Add a ripple's rings, either as sampled polylines or plain circles.
+// Each mirror in `reflections` draws another copy of the rings around the
+// corresponding image source, with the same colors and fade. Rings are
+// point-sampled whenever the style has a wobble or colors that vary along
+// the ring, the ripple is not a plain circle (another shape, or stretched
+// into an ellipse), or obstacles need to shadow them.
+pub fn ripple(out: &mut Vec<Primitive>, ripple: &Ripple, field: &RippleField, style: &RingStyle, reflections: &[Mirror]) {
+    let opacity = ripple.opacity(style.fade_enabled);
+    let along_ring = style.coloring.varies_along_ring();
+    let sampled = style.wobble.is_some() || along_ring || !ripple.is_circle() || !field.obstacles.is_empty();
+
+    for i in 0..ripple.copies {
+        let ring_radius = ripple.ring_radius(i);
+        let [red, green, blue] = ripple.ring_color(i, &style.coloring, 0.0, style.time);
+
+        // Apply opacity
+        let color_with_alpha = [red, green, blue, opacity];
+
+        // Sample the outline once and reuse it for every image
+        let outline = if sampled { ripple.outline_points(i, style.time, style.wobble.as_ref(), &style.noise) } else { Vec::new() };
+
+        // Per-point colors by direction from the center, mirrored along with the rings
+        let point_colors = if along_ring {
+            outline.iter().map(|point| {
+                let degrees = (point.y - ripple.center.y).atan2(point.x - ripple.center.x).to_degrees();
+                let [red, green, blue] = ripple.ring_color(i, &style.coloring, degrees, style.time);
+                [red, green, blue, opacity]
+            }).collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+
+        for mirror in std::iter::once(&Mirror::IDENTITY).chain(reflections) {
+            if sampled {
+                // Points around the outline, dimmed where obstacles cast a shadow
+                let points = outline.iter().map(|&point| mirror.apply(point)).collect::<Vec<_>>();
+                let visibility = field.visibility(ripple, i, &points, mirror);
+                let points = points.iter().zip(visibility).enumerate().map(|(k, (&point, visible))| {
+                    let mut point_color = point_colors.get(k).copied().unwrap_or(color_with_alpha);
+                    point_color[3] *= visible;
+                    (point, point_color)
+                }).collect::<Vec<_>>();
+
+                out.push(Primitive::Polyline { points, weight: ripple.params.stroke_weight });
+            } else {
+                // A regular circle
+                out.push(Primitive::Ring {
+                    center: mirror.apply(ripple.center),
+                    width: ring_radius * 2.0,
+                    height: ring_radius * 2.0,
+                    weight: ripple.params.stroke_weight,
+                    color: color_with_alpha,
+                });
+            }
+        }
+    }
+}
+
+// Add splash droplets in flight, each with a faint shadow on the surface
+// below it so the height reads on screen
+pub fn particles(out: &mut Vec<Primitive>, splashes: &Splashes) {
+    for particle in &splashes.particles {
+        let [red, green, blue] = particle.color.to_f32();
+        out.push(Primitive::Ellipse {
+            center: particle.position,
+            width: particle.size,
+            height: particle.size * 0.5,
+            color: [0.0, 0.0, 0.0, 0.25],
+        });
+        out.push(Primitive::Ellipse {
+            center: particle.draw_position(),
+            width: particle.size,
+            height: particle.size,
+            color: [red, green, blue, 0.9],
+        });
+    }
+}
+
+// A stretch of a polyline drawn in a single color
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorRun {
+    pub points: Vec<Point>,
+    pub color: Rgba,
+    pub closed: bool,                       // Joins back to its first point (a whole one-color ring)
+}
+
+// Split a per-point colored polyline into runs of one color, for backends
+// whose strokes have a single paint. Each point colors the path halfway to
+// its neighbours, so runs meet in the middle of a segment, on a straight
+// line: strokes with butt caps join there edge to edge without overlapping.
+// A polyline that ends where it started, like a sampled ring, is followed
+// all the way round, and comes back as a single closed run if it has one color.
+pub fn color_runs(points: &[(Point, Rgba)]) -> Vec<ColorRun> {
+    if points.len() < 2 {
+        return Vec::new();
+    }
+    let (first, last) = (points[0].0, points[points.len() - 1].0);
+    let closed = points.len() > 3 && first.distance(last) <= CLOSING_DISTANCE;
+    // The last point of a closed polyline repeats the first
+    let points = if closed { &points[..points.len() - 1] } else { points };
+    let count = points.len();
+    if closed && points.iter().all(|&(_, color)| color == points[0].1) {
+        let ring = points.iter().map(|&(point, _)| point).collect();
+        return vec![ColorRun { points: ring, color: points[0].1, closed: true }];
+    }
+
+    let next = |i: usize| if closed { Some((i + 1) % count) } else { (i + 1 < count).then_some(i + 1) };
+    let middle = |i: usize, j: usize| Point::new((points[i].0.x + points[j].0.x) / 2.0, (points[i].0.y + points[j].0.y) / 2.0);
+    // Go round a closed polyline from a color change, so no run wraps past the start
+    let start = if closed {
+        (0..count).find(|&i| points[i].1 != points[(i + count - 1) % count].1).unwrap_or(0)
+    } else {
+        0
+    };
+    let mut runs = Vec::new();
+    let mut run = if closed { vec![middle((start + count - 1) % count, start)] } else { Vec::new() };
+    for i in (start..count).chain(0..start) {
+        run.push(points[i].0);
+        let color = points[i].1;
+        match next(i) {
+            Some(j) if points[j].1 == color => {}
+            Some(j) => {
+                let end = middle(i, j);
+                run.push(end);
+                runs.push(ColorRun { points: std::mem::replace(&mut run, vec![end]), color, closed: false });
+            }
+            None => runs.push(ColorRun { points: std::mem::take(&mut run), color, closed: false }),
+        }
+    }
+    runs
+}
@@ -0,0 +1,239 @@
+use std::fs::{self, File};
+use std::io::{LineWriter, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::emitter::Spawn;
+use crate::geom::Point;
+use crate::ripple::RippleKind;
+
+// Something that happened during a session, as stored in a session file
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Action {
+    Start { seed: u64 },                    // Recording began with this seed
+    Spawn {                                 // A ripple was spawned
+        x: f32,
+        y: f32,
+        kind: RippleKind,
+        strength: f32,
+        stretch: f32,
+        direction: f32,
+    },
+    Impulse { x: f32, y: f32, strength: f32 }, // The wave surface was pushed down
+    Menu { button: String },                // A menu button was pressed
+    Rain { enabled: bool, rate: f32, wind: f32 }, // Rain was changed from the keyboard
+    AddObstacle { obstacle: String },       // An obstacle was placed, as a line of an obstacle file
+    MoveObstacle { index: usize, x: f32, y: f32 }, // An obstacle was moved so its center is at (x, y)
+    DeleteObstacle { index: usize },        // An obstacle was removed
+}
+
+impl Action {
+    pub fn spawn(spawn: &Spawn) -> Self {
+        Action::Spawn {
+            x: spawn.position.x,
+            y: spawn.position.y,
+            kind: spawn.kind,
+            strength: spawn.strength,
+            stretch: spawn.stretch,
+            direction: spawn.direction,
+        }
+    }
+
+    // The spawn request stored in a spawn action
+    pub fn to_spawn(&self) -> Option<Spawn> {
+        match *self {
+            Action::Spawn { x, y, kind, strength, stretch, direction } => {
+                Some(Spawn { position: Point::new(x, y), kind, strength, stretch, direction })
+            }
+            _ => None,
+        }
+    }
+}
+
+// One line of a session file: an action and when it happened, in seconds
+// since the recording started
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Event {
+    pub time: f32,
+    #[serde(flatten)]
+    pub action: Action,
+}
+
+// Writes a session as JSON Lines, one event per line. Every line is flushed
+// as it is written, so a session survives the app being closed abruptly.
+pub struct Recorder {
+    writer: LineWriter<File>,
+    start: f32,                             // Application time the recording started at
+}
+
+impl Recorder {
+    // Start recording to `path` at application time `time`
+    pub fn create(path: &Path, time: f32, seed: u64) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut recorder = Self { writer: LineWriter::new(file), start: time };
+        recorder.record(time, Action::Start { seed })?;
+        Ok(recorder)
+    }
+
+    // Append an action that happened at application time `time`
+    pub fn record(&mut self, time: f32, action: Action) -> Result<(), String> {
+        let event = Event { time: time - self.start, action };
+        let line = serde_json::to_string(&event).map_err(|e| e.to_string())?;
+        writeln!(self.writer, "{}", line).map_err(|e| e.to_string())
+    }
+}
+
+// Parse a session from JSON Lines text. Blank lines are ignored and events
+// are sorted by time.
+pub fn parse(text: &str) -> Result<Vec<Event>, String> {
+    let mut events = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let event = serde_json::from_str::<Event>(line).map_err(|e| format!("line {}: {}", number + 1, e))?;
+        events.push(event);
+    }
+    events.sort_by(|a, b| a.time.total_cmp(&b.time));
+    Ok(events)
+}
+
+// Load a session file in the format written by `Recorder`
+pub fn load(path: &Path) -> Result<Vec<Event>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// Plays a recorded session back, handing out its actions as their time comes
+#[derive(Clone, Debug)]
+pub struct Replay {
+    pub events: Vec<Event>,
+    pub speed: f32,                         // Rate events come at (2 = twice as fast); ripples still grow at their own pace
+    pub looped: bool,                       // Start over after the last event
+    clock: f32,                             // Session time reached so far
+    cursor: usize,                          // Next event to hand out
+}
+
+impl Replay {
+    pub fn new(events: Vec<Event>) -> Self {
+        Self { events, speed: 1.0, looped: false, clock: 0.0, cursor: 0 }
+    }
+
+    // Seed the session was recorded with, if it says
+    pub fn seed(&self) -> Option<u64> {
+        self.events.iter().find_map(|event| match event.action {
+            Action::Start { seed } => Some(seed),
+            _ => None,
+        })
+    }
+
+    // Session time of the last event
+    pub fn duration(&self) -> f32 {
+        self.events.last().map_or(0.0, |event| event.time)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        !self.looped && self.cursor >= self.events.len()
+    }
+
+    // Advance by `dt` seconds of real time. Returns the actions that fell
+    // due, in order; a looped replay wraps around after its last event.
+    pub fn update(&mut self, dt: f32) -> Vec<Action> {
+        let mut due = Vec::new();
+        if self.events.is_empty() {
+            return due;
+        }
+        self.clock += dt * self.speed;
+        loop {
+            while let Some(event) = self.events.get(self.cursor) && event.time <= self.clock {
+                due.push(event.action.clone());
+                self.cursor += 1;
+            }
+            // Stop unless looping past the end; a zero length session only plays once per update
+            let duration = self.duration();
+            if !self.looped || self.cursor < self.events.len() || self.clock < duration || duration <= 0.0 {
+                break;
+            }
+            self.clock -= duration;
+            self.cursor = 0;
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SESSION: &str = r#"
+{"time":0.0,"type":"start","seed":42}
+{"time":1.2,"type":"menu","button":"wobble"}
+
+{"time":0.5,"type":"spawn","x":-120.0,"y":40.0,"kind":"drop","strength":1.0,"stretch":1.0,"direction":0.0}
+{"time":2.0,"type":"add_obstacle","obstacle":"circle 150 -80 40"}
+"#;
+
+    #[test]
+    fn parses_events_sorted_by_time() {
+        let events = parse(SESSION).unwrap();
+        let times: Vec<f32> = events.iter().map(|event| event.time).collect();
+        assert_eq!(times, [0.0, 0.5, 1.2, 2.0]);
+        assert_eq!(events[1].action.to_spawn(), Some(Spawn::new(Point::new(-120.0, 40.0), RippleKind::Drop)));
+        assert_eq!(events[2].action, Action::Menu { button: "wobble".to_string() });
+        assert_eq!(Replay::new(events).seed(), Some(42));
+    }
+
+    #[test]
+    fn reports_the_line_of_a_bad_event() {
+        let error = parse("{\"time\":0.0,\"type\":\"start\",\"seed\":1}\n{\"time\":1.0,\"type\":\"jump\"}").unwrap_err();
+        assert!(error.starts_with("line 2:"), "{}", error);
+    }
+
+    #[test]
+    fn recorded_sessions_read_back() {
+        let path = std::env::temp_dir().join(format!("waves-session-{}.jsonl", std::process::id()));
+        let spawn = Spawn { strength: 1.7, stretch: 1.2, direction: 0.3, ..Spawn::new(Point::new(3.5, -8.25), RippleKind::Swell) };
+        let mut recorder = Recorder::create(&path, 10.0, 7).unwrap();
+        recorder.record(10.5, Action::spawn(&spawn)).unwrap();
+        recorder.record(11.0, Action::Rain { enabled: true, rate: 8.0, wind: -0.5 }).unwrap();
+        recorder.record(11.5, Action::DeleteObstacle { index: 2 }).unwrap();
+        drop(recorder);
+
+        let events = load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(events, [
+            Event { time: 0.0, action: Action::Start { seed: 7 } },
+            Event { time: 0.5, action: Action::spawn(&spawn) },
+            Event { time: 1.0, action: Action::Rain { enabled: true, rate: 8.0, wind: -0.5 } },
+            Event { time: 1.5, action: Action::DeleteObstacle { index: 2 } },
+        ]);
+    }
+
+    #[test]
+    fn replays_hand_out_actions_as_their_time_comes() {
+        let mut replay = Replay::new(parse(SESSION).unwrap());
+        replay.speed = 2.0;
+        assert_eq!(replay.update(0.0).len(), 1);
+        assert_eq!(replay.update(0.6).len(), 2);
+        assert!(!replay.is_finished());
+        assert_eq!(replay.update(0.5).len(), 1);
+        assert!(replay.is_finished());
+        assert!(replay.update(1.0).is_empty());
+    }
+
+    #[test]
+    fn looped_replays_start_over_after_the_last_event() {
+        let mut replay = Replay::new(parse(SESSION).unwrap());
+        replay.looped = true;
+        assert_eq!(replay.update(1.9).len(), 3);
+        // 0.5 s past the last event is 0.5 s into the next pass
+        let actions = replay.update(0.6);
+        assert_eq!(actions.len(), 3);
+        assert_eq!(actions[1], Action::Start { seed: 42 });
+        assert!(actions[2].to_spawn().is_some());
+        assert!(!replay.is_finished());
+    }
+}
@@ -0,0 +1,336 @@
+use noise::Perlin;
+
+use crate::background::BackgroundType;
+use crate::color::Interpolation;
+use crate::field::RippleField;
+use crate::rainbow::{HueModel, Rainbow};
+use crate::reflection::Edges;
+use crate::ripple::{Coloring, RippleKind};
+use crate::scene::RingStyle;
+use crate::shape::RippleShape;
+use crate::splash;
+use crate::wobble::{Wobble, WobbleMode};
+
+// Choices cycled through by the wobble buttons
+pub const WOBBLE_AMPLITUDES: [f32; 4] = [0.8, 2.0, 4.0, 8.0];       // Peak radius change in percent
+pub const WOBBLE_FREQUENCIES: [f32; 4] = [0.75, 1.5, 3.0, 6.0];     // Bumps around a ring (noise mode)
+pub const WOBBLE_SPEEDS: [f32; 4] = [0.5, 1.0, 2.0, 4.0];           // Animation speed
+
+// Choices cycled through by the rainbow buttons
+pub const SATURATION_LEVELS: [f32; 4] = [0.4, 0.6, 0.8, 1.0];
+pub const LIGHTNESS_LEVELS: [f32; 5] = [0.4, 0.5, 0.6, 0.7, 0.8];
+pub const HUE_SPEEDS: [f32; 5] = [0.0, 0.05, 0.1, 0.25, 0.5];       // Turns of the color wheel per second
+
+// Trail persistence choices: seconds for a trail to fade to about a third
+pub const TRAIL_PERSISTENCES: [f32; 4] = [0.25, 0.5, 1.0, 2.0];
+
+// Ring coloring offered in the menu
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ColorMode {
+    Fixed,      // One palette color per ring
+    Lifetime,   // Palette colors blended as rings grow
+    Rainbow,    // Rotating rainbow around each ring
+}
+
+// Every button inside the menu panel. Sessions record presses by name, so
+// the headless renderer can replay the ones that change the scene.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum MenuButton {
+    Wobble,
+    WobbleMode,
+    WobbleAmplitude,
+    WobbleFrequency,
+    WobbleSpeed,
+    Fade,
+    Colors,
+    Blend,
+    HueModel,
+    Saturation,
+    Lightness,
+    HueSpeed,
+    Palette,
+    ColorPick,
+    Background,
+    Trails,
+    TrailPersistence,
+    Splash,
+    SplashPalette,
+    Rain,
+    RainRate,
+    DropSize,
+    Wind,
+    Attract,
+    IdleTimeout,
+    Mode,
+    GridResolution,
+    Damping,
+    Reflect,
+    ReflectLeft,
+    ReflectRight,
+    ReflectTop,
+    ReflectBottom,
+    ObstacleSelect,
+    ObstacleMove,
+    ObstacleDelete,
+    EditKind,
+    RadiusEasing,
+    AlphaEasing,
+    Velocity,
+    Stretch,
+    Spacing,
+    Shape,
+    Seed,
+}
+
+impl MenuButton {
+    // Name of the button as written to session files. These are part of
+    // the file format, so they must not change when variants are renamed.
+    pub fn name(self) -> &'static str {
+        match self {
+            MenuButton::Wobble => "wobble",
+            MenuButton::WobbleMode => "wobble_mode",
+            MenuButton::WobbleAmplitude => "wobble_amplitude",
+            MenuButton::WobbleFrequency => "wobble_frequency",
+            MenuButton::WobbleSpeed => "wobble_speed",
+            MenuButton::Fade => "fade",
+            MenuButton::Colors => "colors",
+            MenuButton::Blend => "blend",
+            MenuButton::HueModel => "hue_model",
+            MenuButton::Saturation => "saturation",
+            MenuButton::Lightness => "lightness",
+            MenuButton::HueSpeed => "hue_speed",
+            MenuButton::Palette => "palette",
+            MenuButton::ColorPick => "color_pick",
+            MenuButton::Background => "background",
+            MenuButton::Trails => "trails",
+            MenuButton::TrailPersistence => "trail_persistence",
+            MenuButton::Splash => "splash",
+            MenuButton::SplashPalette => "splash_palette",
+            MenuButton::Rain => "rain",
+            MenuButton::RainRate => "rain_rate",
+            MenuButton::DropSize => "drop_size",
+            MenuButton::Wind => "wind",
+            MenuButton::Attract => "attract",
+            MenuButton::IdleTimeout => "idle_timeout",
+            MenuButton::Mode => "mode",
+            MenuButton::GridResolution => "grid_resolution",
+            MenuButton::Damping => "damping",
+            MenuButton::Reflect => "reflect",
+            MenuButton::ReflectLeft => "reflect_left",
+            MenuButton::ReflectRight => "reflect_right",
+            MenuButton::ReflectTop => "reflect_top",
+            MenuButton::ReflectBottom => "reflect_bottom",
+            MenuButton::ObstacleSelect => "obstacle_select",
+            MenuButton::ObstacleMove => "obstacle_move",
+            MenuButton::ObstacleDelete => "obstacle_delete",
+            MenuButton::EditKind => "edit_kind",
+            MenuButton::RadiusEasing => "radius_easing",
+            MenuButton::AlphaEasing => "alpha_easing",
+            MenuButton::Velocity => "velocity",
+            MenuButton::Stretch => "stretch",
+            MenuButton::Spacing => "spacing",
+            MenuButton::Shape => "shape",
+            MenuButton::Seed => "seed",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<MenuButton> {
+        MenuButton::ALL.iter().copied().find(|button| button.name() == name)
+    }
+
+    pub const ALL: [MenuButton; 44] = [
+        MenuButton::Wobble,
+        MenuButton::WobbleMode,
+        MenuButton::WobbleAmplitude,
+        MenuButton::WobbleFrequency,
+        MenuButton::WobbleSpeed,
+        MenuButton::Fade,
+        MenuButton::Colors,
+        MenuButton::Blend,
+        MenuButton::HueModel,
+        MenuButton::Saturation,
+        MenuButton::Lightness,
+        MenuButton::HueSpeed,
+        MenuButton::Palette,
+        MenuButton::ColorPick,
+        MenuButton::Background,
+        MenuButton::Trails,
+        MenuButton::TrailPersistence,
+        MenuButton::Splash,
+        MenuButton::SplashPalette,
+        MenuButton::Rain,
+        MenuButton::RainRate,
+        MenuButton::DropSize,
+        MenuButton::Wind,
+        MenuButton::Attract,
+        MenuButton::IdleTimeout,
+        MenuButton::Mode,
+        MenuButton::GridResolution,
+        MenuButton::Damping,
+        MenuButton::Reflect,
+        MenuButton::ReflectLeft,
+        MenuButton::ReflectRight,
+        MenuButton::ReflectTop,
+        MenuButton::ReflectBottom,
+        MenuButton::ObstacleSelect,
+        MenuButton::ObstacleMove,
+        MenuButton::ObstacleDelete,
+        MenuButton::EditKind,
+        MenuButton::RadiusEasing,
+        MenuButton::AlphaEasing,
+        MenuButton::Velocity,
+        MenuButton::Stretch,
+        MenuButton::Spacing,
+        MenuButton::Shape,
+        MenuButton::Seed,
+    ];
+}
+
+// How frames look, as set from the menu: wobble, fade, colors, background,
+// trails and reflections. The window's menu wraps one and the headless
+// renderer keeps its own, so both draw a scene the same way.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SceneSettings {
+    pub wobble_enabled: bool,
+    pub wobble: Wobble,                     // Wobble style and strength, used while wobble is enabled
+    pub fade_enabled: bool,
+    pub color_mode: ColorMode,              // Where ring colors come from
+    pub interpolation: Interpolation,       // Color space the lifetime gradient blends in
+    pub rainbow: Rainbow,                   // Hue model, saturation, lightness and speed of rainbow rings
+    pub background_type: BackgroundType,
+    pub trails_enabled: bool,               // Fade the previous frame instead of clearing it
+    pub trail_persistence: f32,             // Time constant of the trail fade, in seconds
+    pub reflect_enabled: bool,
+    pub reflect_edges: Edges,
+    pub edited_kind: RippleKind,            // Ripple kind whose curves the easing buttons change
+}
+
+impl SceneSettings {
+    pub fn new(wobble_enabled: bool, fade_enabled: bool) -> Self {
+        Self {
+            wobble_enabled,
+            wobble: Wobble::DEFAULT,
+            fade_enabled,
+            color_mode: ColorMode::Fixed,
+            interpolation: Interpolation::Oklab,
+            rainbow: Rainbow::DEFAULT,
+            background_type: BackgroundType::Water,
+            trails_enabled: false,
+            trail_persistence: TRAIL_PERSISTENCES[1],
+            reflect_enabled: false,
+            reflect_edges: Edges::ALL,
+            edited_kind: RippleKind::Drop,
+        }
+    }
+
+    // Ring drawing settings at application time `time`
+    pub fn ring_style(&self, time: f32, noise: Perlin) -> RingStyle {
+        RingStyle {
+            time,
+            wobble: self.wobble_enabled.then_some(self.wobble),
+            noise,
+            fade_enabled: self.fade_enabled,
+            coloring: self.coloring(),
+        }
+    }
+
+    // Ring coloring selected by the color buttons
+    pub fn coloring(&self) -> Coloring {
+        match self.color_mode {
+            ColorMode::Fixed => Coloring::Fixed,
+            ColorMode::Lifetime => Coloring::Lifetime(self.interpolation),
+            ColorMode::Rainbow => Coloring::Rainbow(self.rainbow),
+        }
+    }
+
+    // Window edges rings bounce off, if reflections are on
+    pub fn reflect(&self) -> Option<Edges> {
+        self.reflect_enabled.then_some(self.reflect_edges)
+    }
+
+    // Apply a click on a button that changes how the scene looks, including
+    // the field's palette, splash, shape and easing settings. Returns false
+    // for the other buttons, which the front end handles itself.
+    pub fn press(&mut self, field: &mut RippleField, shapes: &[RippleShape], button: MenuButton) -> bool {
+        match button {
+            MenuButton::Wobble => self.wobble_enabled = !self.wobble_enabled,
+            MenuButton::WobbleMode => {
+                self.wobble.mode = match self.wobble.mode {
+                    WobbleMode::Sine => WobbleMode::Noise,
+                    WobbleMode::Noise => WobbleMode::Sine,
+                };
+            }
+            MenuButton::WobbleAmplitude => self.wobble.amplitude = next_value(&WOBBLE_AMPLITUDES, self.wobble.amplitude),
+            MenuButton::WobbleFrequency => self.wobble.frequency = next_value(&WOBBLE_FREQUENCIES, self.wobble.frequency),
+            MenuButton::WobbleSpeed => self.wobble.speed = next_value(&WOBBLE_SPEEDS, self.wobble.speed),
+            MenuButton::Fade => self.fade_enabled = !self.fade_enabled,
+            MenuButton::Colors => {
+                self.color_mode = match self.color_mode {
+                    ColorMode::Fixed => ColorMode::Lifetime,
+                    ColorMode::Lifetime => ColorMode::Rainbow,
+                    ColorMode::Rainbow => ColorMode::Fixed,
+                };
+            }
+            MenuButton::Blend => self.interpolation = self.interpolation.next(),
+            MenuButton::HueModel => {
+                self.rainbow.model = match self.rainbow.model {
+                    HueModel::Hsl => HueModel::Lch,
+                    HueModel::Lch => HueModel::Hsl,
+                };
+            }
+            MenuButton::Saturation => self.rainbow.saturation = next_value(&SATURATION_LEVELS, self.rainbow.saturation),
+            MenuButton::Lightness => self.rainbow.lightness = next_value(&LIGHTNESS_LEVELS, self.rainbow.lightness),
+            MenuButton::HueSpeed => self.rainbow.speed = next_value(&HUE_SPEEDS, self.rainbow.speed),
+            MenuButton::Palette => field.palettes.select_next(),
+            MenuButton::ColorPick => field.picker.selection = field.picker.selection.next(),
+            MenuButton::Background => self.background_type = self.background_type.next(),
+            MenuButton::Trails => self.trails_enabled = !self.trails_enabled,
+            MenuButton::TrailPersistence => {
+                self.trail_persistence = next_value(&TRAIL_PERSISTENCES, self.trail_persistence);
+            }
+            MenuButton::Splash => field.splashes.enabled = !field.splashes.enabled,
+            MenuButton::SplashPalette => {
+                // Cycle the default spray colors followed by every loaded palette
+                let choices = std::iter::once(splash::spray_palette())
+                    .chain(field.palettes.palettes().iter().cloned())
+                    .collect::<Vec<_>>();
+                let current = choices.iter().position(|palette| palette.name == field.splashes.palette.name).unwrap_or(0);
+                field.splashes.palette = choices[(current + 1) % choices.len()].clone();
+            }
+            MenuButton::Reflect => self.reflect_enabled = !self.reflect_enabled,
+            MenuButton::ReflectLeft => self.reflect_edges.left = !self.reflect_edges.left,
+            MenuButton::ReflectRight => self.reflect_edges.right = !self.reflect_edges.right,
+            MenuButton::ReflectTop => self.reflect_edges.top = !self.reflect_edges.top,
+            MenuButton::ReflectBottom => self.reflect_edges.bottom = !self.reflect_edges.bottom,
+            MenuButton::EditKind => {
+                let index = RippleKind::ALL.iter().position(|&k| k == self.edited_kind).unwrap_or(0);
+                self.edited_kind = RippleKind::ALL[(index + 1) % RippleKind::ALL.len()];
+            }
+            // Cycle the curves of the ripple kind being edited
+            MenuButton::RadiusEasing => {
+                let params = field.params_mut(self.edited_kind);
+                params.radius_easing = params.radius_easing.next();
+            }
+            MenuButton::AlphaEasing => {
+                let params = field.params_mut(self.edited_kind);
+                params.alpha_easing = params.alpha_easing.next();
+            }
+            // Cycle the outline given to new ripples
+            MenuButton::Shape => {
+                let index = shapes.iter().position(|shape| *shape == field.shape).unwrap_or(0);
+                field.shape = shapes[(index + 1) % shapes.len()].clone();
+            }
+            MenuButton::Rain | MenuButton::RainRate | MenuButton::DropSize | MenuButton::Wind
+            | MenuButton::Attract | MenuButton::IdleTimeout | MenuButton::Mode | MenuButton::GridResolution
+            | MenuButton::Damping | MenuButton::ObstacleSelect | MenuButton::ObstacleMove | MenuButton::ObstacleDelete
+            | MenuButton::Velocity | MenuButton::Stretch | MenuButton::Spacing | MenuButton::Seed => return false,
+        }
+        true
+    }
+}
+
+// Pick the entry after `current` in `values`, wrapping around
+pub fn next_value(values: &[f32], current: f32) -> f32 {
+    let index = values.iter().position(|&v| v == current).unwrap_or(0);
+    values[(index + 1) % values.len()]
+}
@@ -0,0 +1,427 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use crate::geom::Point;
+
+// Outline sampling
+pub const OUTLINE_SAMPLES: usize = 72;      // Points around a parametric outline (every 5 degrees for a circle)
+pub const MAX_SEGMENT: f32 = TAU / 72.0;    // Longest straight piece of a unit outline, so wobble stays smooth
+pub const CURVE_SEGMENTS: usize = 16;       // Line segments per Bezier curve or arc in an SVG path
+
+// Outline every ring of a ripple follows. Shapes are described at unit size
+// (farthest point 1 from the center) and scaled to each ring's radius.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum RippleShape {
+    #[default]
+    Circle,
+    Ellipse { aspect: f32 },                // Height relative to width
+    Polygon { sides: usize },               // Regular polygon with a corner at the top
+    Star { points: usize, inner: f32 },     // Inner corners at `inner` times the outer radius
+    Heart,
+    Path { points: Vec<Point> },            // Closed path from an SVG path string, already at unit size
+}
+
+impl RippleShape {
+    // Shapes offered in the menu, in cycling order
+    pub const PRESETS: [RippleShape; 7] = [
+        RippleShape::Circle,
+        RippleShape::Ellipse { aspect: 0.6 },
+        RippleShape::Polygon { sides: 3 },
+        RippleShape::Polygon { sides: 4 },
+        RippleShape::Polygon { sides: 6 },
+        RippleShape::Star { points: 5, inner: 0.5 },
+        RippleShape::Heart,
+    ];
+
+    // Build a shape from SVG path data (the `d` attribute), e.g.
+    // "M 0 -10 L 10 10 L -10 10 Z". Only the first subpath is used.
+    pub fn from_svg_path(data: &str) -> Result<RippleShape, String> {
+        let points = parse_svg_path(data)?;
+        if points.len() < 3 {
+            return Err("path needs at least three points".to_string());
+        }
+        // SVG has y pointing down, the simulation has y pointing up
+        let flipped: Vec<Point> = points.iter().map(|p| Point::new(p.x, -p.y)).collect();
+        Ok(RippleShape::Path { points: normalize(&flipped) })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RippleShape::Circle => "CIRCLE",
+            RippleShape::Ellipse { .. } => "ELLIPSE",
+            RippleShape::Polygon { sides: 3 } => "TRIANGLE",
+            RippleShape::Polygon { sides: 4 } => "SQUARE",
+            RippleShape::Polygon { sides: 5 } => "PENTAGON",
+            RippleShape::Polygon { sides: 6 } => "HEXAGON",
+            RippleShape::Polygon { .. } => "POLYGON",
+            RippleShape::Star { .. } => "STAR",
+            RippleShape::Heart => "HEART",
+            RippleShape::Path { .. } => "PATH",
+        }
+    }
+
+    // Closed outline at unit size around the origin; the last point repeats the first
+    pub fn unit_outline(&self) -> Vec<Point> {
+        match self {
+            RippleShape::Circle => parametric(|angle| Point::new(angle.cos(), angle.sin())),
+            RippleShape::Ellipse { aspect } => {
+                let (width, height) = if *aspect > 1.0 { (1.0 / aspect, 1.0) } else { (1.0, *aspect) };
+                parametric(|angle| Point::new(width * angle.cos(), height * angle.sin()))
+            }
+            RippleShape::Polygon { sides } => {
+                let sides = (*sides).max(3);
+                let corners = (0..sides).map(|i| corner(i, sides, 1.0)).collect::<Vec<_>>();
+                subdivide(&corners)
+            }
+            RippleShape::Star { points, inner } => {
+                let count = (*points).max(2) * 2;
+                let corners = (0..count)
+                    .map(|i| corner(i, count, if i % 2 == 0 { 1.0 } else { *inner }))
+                    .collect::<Vec<_>>();
+                subdivide(&corners)
+            }
+            RippleShape::Heart => {
+                // Classic heart curve, centered and scaled afterwards
+                let points = parametric(|t| Point::new(
+                    16.0 * t.sin().powi(3),
+                    13.0 * t.cos() - 5.0 * (2.0 * t).cos() - 2.0 * (3.0 * t).cos() - (4.0 * t).cos(),
+                ));
+                normalize(&points)
+            }
+            RippleShape::Path { points } => subdivide(points),
+        }
+    }
+}
+
+// Sample a closed curve over one turn of its parameter
+fn parametric(curve: impl Fn(f32) -> Point) -> Vec<Point> {
+    (0..=OUTLINE_SAMPLES)
+        .map(|i| curve(i as f32 / OUTLINE_SAMPLES as f32 * TAU))
+        .collect()
+}
+
+// Corner `index` of `count` evenly spaced around the center, starting at the top
+fn corner(index: usize, count: usize, radius: f32) -> Point {
+    let angle = FRAC_PI_2 + index as f32 / count as f32 * TAU;
+    Point::new(radius * angle.cos(), radius * angle.sin())
+}
+
+// Close a polygon and split long edges so wobble can bend them
+fn subdivide(corners: &[Point]) -> Vec<Point> {
+    let mut points = Vec::new();
+    for (i, &from) in corners.iter().enumerate() {
+        let to = corners[(i + 1) % corners.len()];
+        let steps = (from.distance(to) / MAX_SEGMENT).ceil().max(1.0) as usize;
+        for step in 0..steps {
+            points.push(lerp(from, to, step as f32 / steps as f32));
+        }
+    }
+    points.extend(corners.first());
+    points
+}
+
+// Center points on their bounding box and scale the farthest one to distance 1
+fn normalize(points: &[Point]) -> Vec<Point> {
+    let (mut left, mut right, mut bottom, mut top) = (f32::MAX, f32::MIN, f32::MAX, f32::MIN);
+    for p in points {
+        left = left.min(p.x);
+        right = right.max(p.x);
+        bottom = bottom.min(p.y);
+        top = top.max(p.y);
+    }
+    let center = Point::new((left + right) / 2.0, (bottom + top) / 2.0);
+    let extent = points.iter().map(|p| p.distance(center)).fold(0.0, f32::max).max(f32::EPSILON);
+    points.iter().map(|p| Point::new((p.x - center.x) / extent, (p.y - center.y) / extent)).collect()
+}
+
+fn lerp(a: Point, b: Point, t: f32) -> Point {
+    Point::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t)
+}
+
+// Piece of SVG path data
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token {
+    Command(char),
+    Number(f32),
+}
+
+// Split path data into commands and numbers. Numbers may be separated by
+// whitespace, commas, a sign or a second decimal point ("1.5.5" is 1.5 then .5).
+fn tokenize(data: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = data.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() || c == ',' {
+            i += 1;
+        } else if "MmLlHhVvCcSsQqTtAaZz".contains(c) {
+            tokens.push(Token::Command(c));
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' || c == '-' || c == '+' {
+            let start = i;
+            if c == '-' || c == '+' {
+                i += 1;
+            }
+            let mut seen_dot = false;
+            while i < chars.len() && (chars[i].is_ascii_digit() || (chars[i] == '.' && !seen_dot)) {
+                seen_dot |= chars[i] == '.';
+                i += 1;
+            }
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                i += 1;
+                if i < chars.len() && (chars[i] == '-' || chars[i] == '+') {
+                    i += 1;
+                }
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = text.parse::<f32>().map_err(|_| format!("'{}' is not a number", text))?;
+            tokens.push(Token::Number(value));
+        } else {
+            return Err(format!("unexpected '{}' in path", c));
+        }
+    }
+    Ok(tokens)
+}
+
+// Flatten the first subpath of SVG path data into points, in SVG
+// coordinates. Curves and arcs are split into `CURVE_SEGMENTS` lines.
+pub fn parse_svg_path(data: &str) -> Result<Vec<Point>, String> {
+    let tokens = tokenize(data)?;
+    let mut points: Vec<Point> = Vec::new();
+    let mut current = Point::default();
+    let mut control: Option<(char, Point)> = None; // Last curve family and control point, reflected by S and T
+    let mut command: Option<char> = None;
+    let mut i = 0;
+
+    while i < tokens.len() {
+        if let Token::Command(c) = tokens[i] {
+            i += 1;
+            if c == 'Z' || c == 'z' {
+                break;
+            }
+            // A second moveto starts another subpath, which is ignored
+            if (c == 'M' || c == 'm') && !points.is_empty() {
+                break;
+            }
+            command = Some(c);
+        }
+        let c = command.ok_or("path must start with a command")?;
+        let count = match c.to_ascii_uppercase() {
+            'M' | 'L' | 'T' => 2,
+            'H' | 'V' => 1,
+            'S' | 'Q' => 4,
+            'C' => 6,
+            _ => 7,
+        };
+        let mut values = Vec::with_capacity(count);
+        for token in tokens.iter().skip(i).take(count) {
+            match token {
+                Token::Number(value) => values.push(*value),
+                Token::Command(_) => break,
+            }
+        }
+        if values.len() < count {
+            return Err(format!("'{}' needs {} numbers", c, count));
+        }
+        i += count;
+
+        // Relative commands are offsets from the current point
+        let origin = if c.is_ascii_lowercase() { current } else { Point::default() };
+        let at = |index: usize| Point::new(origin.x + values[index], origin.y + values[index + 1]);
+        // S continues a cubic and T a quadratic; after anything else they start at the current point
+        let family = if "CcSs".contains(c) { 'C' } else { 'Q' };
+        let reflected = match control {
+            Some((previous, p)) if previous == family => Point::new(2.0 * current.x - p.x, 2.0 * current.y - p.y),
+            _ => current,
+        };
+        control = None;
+
+        match c.to_ascii_uppercase() {
+            'M' => {
+                current = at(0);
+                points.push(current);
+                // Further pairs after a moveto are linetos
+                command = Some(if c == 'm' { 'l' } else { 'L' });
+            }
+            'L' => {
+                current = at(0);
+                points.push(current);
+            }
+            'H' => {
+                current = Point::new(origin.x + values[0], current.y);
+                points.push(current);
+            }
+            'V' => {
+                current = Point::new(current.x, origin.y + values[0]);
+                points.push(current);
+            }
+            'C' | 'S' => {
+                let (first, rest) = if c.eq_ignore_ascii_case(&'C') { (at(0), 2) } else { (reflected, 0) };
+                let (second, end) = (at(rest), at(rest + 2));
+                points.extend(cubic(current, first, second, end));
+                control = Some(('C', second));
+                current = end;
+            }
+            'Q' | 'T' => {
+                let (handle, end) = if c.eq_ignore_ascii_case(&'Q') { (at(0), at(2)) } else { (reflected, at(0)) };
+                points.extend(quadratic(current, handle, end));
+                control = Some(('Q', handle));
+                current = end;
+            }
+            _ => {
+                let end = at(5);
+                points.extend(arc(current, values[0], values[1], values[2], values[3] != 0.0, values[4] != 0.0, end));
+                current = end;
+            }
+        }
+    }
+
+    // Drop the closing point if the path returned to its start explicitly
+    if points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    Ok(points)
+}
+
+fn cubic(start: Point, first: Point, second: Point, end: Point) -> Vec<Point> {
+    (1..=CURVE_SEGMENTS).map(|i| {
+        let t = i as f32 / CURVE_SEGMENTS as f32;
+        let inv = 1.0 - t;
+        let (a, b, c, d) = (inv * inv * inv, 3.0 * inv * inv * t, 3.0 * inv * t * t, t * t * t);
+        Point::new(
+            a * start.x + b * first.x + c * second.x + d * end.x,
+            a * start.y + b * first.y + c * second.y + d * end.y,
+        )
+    }).collect()
+}
+
+fn quadratic(start: Point, handle: Point, end: Point) -> Vec<Point> {
+    (1..=CURVE_SEGMENTS).map(|i| {
+        let t = i as f32 / CURVE_SEGMENTS as f32;
+        lerp(lerp(start, handle, t), lerp(handle, end, t), t)
+    }).collect()
+}
+
+// Elliptical arc from `start` to `end`, converted to center form as in the
+// SVG implementation notes
+fn arc(start: Point, rx: f32, ry: f32, rotation: f32, large: bool, sweep: bool, end: Point) -> Vec<Point> {
+    let (mut rx, mut ry) = (rx.abs(), ry.abs());
+    if rx == 0.0 || ry == 0.0 || start == end {
+        return vec![end];
+    }
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    let (dx, dy) = ((start.x - end.x) / 2.0, (start.y - end.y) / 2.0);
+    let (x1, y1) = (cos * dx + sin * dy, -sin * dx + cos * dy);
+
+    // Grow radii that are too small to span the endpoints
+    let scale = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if scale > 1.0 {
+        rx *= scale.sqrt();
+        ry *= scale.sqrt();
+    }
+    let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+    let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let sign = if large == sweep { -1.0 } else { 1.0 };
+    let factor = sign * (numerator / denominator).max(0.0).sqrt();
+    let (cx1, cy1) = (factor * rx * y1 / ry, -factor * ry * x1 / rx);
+    let center = Point::new(
+        cos * cx1 - sin * cy1 + (start.x + end.x) / 2.0,
+        sin * cx1 + cos * cy1 + (start.y + end.y) / 2.0,
+    );
+
+    let start_angle = ((y1 - cy1) / ry).atan2((x1 - cx1) / rx);
+    let end_angle = ((-y1 - cy1) / ry).atan2((-x1 - cx1) / rx);
+    let mut sweep_angle = end_angle - start_angle;
+    if sweep && sweep_angle < 0.0 {
+        sweep_angle += TAU;
+    } else if !sweep && sweep_angle > 0.0 {
+        sweep_angle -= TAU;
+    }
+
+    (1..=CURVE_SEGMENTS).map(|i| {
+        let angle = start_angle + sweep_angle * i as f32 / CURVE_SEGMENTS as f32;
+        let (x, y) = (rx * angle.cos(), ry * angle.sin());
+        Point::new(center.x + cos * x - sin * y, center.y + sin * x + cos * y)
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn near(a: Point, b: Point) -> bool {
+        a.distance(b) < 1e-4
+    }
+
+    #[test]
+    fn parses_straight_lines() {
+        let square = [Point::new(0.0, 0.0), Point::new(10.0, 0.0), Point::new(10.0, 10.0), Point::new(0.0, 10.0)];
+        // Absolute and relative commands, implicit linetos and compact numbers
+        for data in ["M 0 0 L 10 0 L 10 10 L 0 10 Z", "m0,0 10,0 0,10 -10,0 z", "M0 0H10V10H0", "M0-0l10 0v10h-10L0 0"] {
+            assert_eq!(parse_svg_path(data).unwrap(), square, "{}", data);
+        }
+        assert_eq!(parse_svg_path("M.5.5L1e1-2").unwrap(), [Point::new(0.5, 0.5), Point::new(10.0, -2.0)]);
+    }
+
+    #[test]
+    fn uses_only_the_first_subpath() {
+        let points = parse_svg_path("M 0 0 L 10 0 L 10 10 Z M 50 50 L 60 50").unwrap();
+        assert_eq!(points.len(), 3);
+        let points = parse_svg_path("M 0 0 L 10 0 L 10 10 M 50 50 L 60 50").unwrap();
+        assert_eq!(points.last(), Some(&Point::new(10.0, 10.0)));
+    }
+
+    #[test]
+    fn flattens_curves() {
+        let points = parse_svg_path("M 0 0 C 0 10 10 10 10 0 S 20 -10 20 0").unwrap();
+        assert_eq!(points.len(), 1 + 2 * CURVE_SEGMENTS);
+        assert_eq!(points[CURVE_SEGMENTS], Point::new(10.0, 0.0));
+        // S reflects the previous control point, so the second half dips below
+        assert!(near(points[CURVE_SEGMENTS + CURVE_SEGMENTS / 2], Point::new(15.0, -7.5)));
+
+        let points = parse_svg_path("M 0 0 Q 5 10 10 0 T 20 0").unwrap();
+        assert!(near(points[CURVE_SEGMENTS / 2], Point::new(5.0, 5.0)));
+        assert!(near(points[CURVE_SEGMENTS + CURVE_SEGMENTS / 2], Point::new(15.0, -5.0)));
+    }
+
+    #[test]
+    fn flattens_arcs() {
+        let points = parse_svg_path("M -10 0 A 10 10 0 0 1 10 0").unwrap();
+        assert_eq!(points.len(), 1 + CURVE_SEGMENTS);
+        assert!(points.iter().all(|p| (p.length() - 10.0).abs() < 1e-3));
+        assert!(near(points[CURVE_SEGMENTS], Point::new(10.0, 0.0)));
+        // Sweeping the other way takes the other half of the circle
+        let other = parse_svg_path("M -10 0 A 10 10 0 0 0 10 0").unwrap();
+        assert!(near(points[CURVE_SEGMENTS / 2], Point::new(-other[CURVE_SEGMENTS / 2].x, -other[CURVE_SEGMENTS / 2].y)));
+        // Too small radii are grown to span the endpoints
+        let points = parse_svg_path("M 0 0 A 1 1 0 0 1 20 0").unwrap();
+        assert!(near(points[CURVE_SEGMENTS / 2], Point::new(10.0, -10.0)) || near(points[CURVE_SEGMENTS / 2], Point::new(10.0, 10.0)));
+    }
+
+    #[test]
+    fn reports_bad_path_data() {
+        assert_eq!(parse_svg_path("0 0 L 1 1"), Err("path must start with a command".to_string()));
+        assert_eq!(parse_svg_path("M 0 0 L 1"), Err("'L' needs 2 numbers".to_string()));
+        assert_eq!(parse_svg_path("M 0 0 X 1"), Err("unexpected 'X' in path".to_string()));
+        assert_eq!(parse_svg_path("M 0 0 L 1 -"), Err("'-' is not a number".to_string()));
+        assert!(RippleShape::from_svg_path("M 0 0 L 10 0 Z").is_err());
+    }
+
+    #[test]
+    fn path_shapes_are_flipped_and_unit_sized() {
+        let shape = RippleShape::from_svg_path("M 0 -10 L 10 10 L -10 10 Z").unwrap();
+        let RippleShape::Path { points } = &shape else {
+            panic!("expected a path, got {:?}", shape);
+        };
+        let farthest = points.iter().map(|p| p.length()).fold(0.0, f32::max);
+        assert!((farthest - 1.0).abs() < 1e-5);
+        // The tip at the top of the SVG stays on top with y up
+        assert!(points[0].y > 0.0 && points[1].y < 0.0);
+        let outline = shape.unit_outline();
+        assert_eq!(outline.first(), outline.last());
+        assert!(outline.windows(2).all(|pair| pair[0].distance(pair[1]) <= MAX_SEGMENT + 1e-5));
+    }
+}
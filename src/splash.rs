@@ -0,0 +1,189 @@
+use rand::Rng;
+
+use crate::color::Color;
+use crate::geom::Point;
+use crate::palette::{ColorPicker, Palette};
+use crate::random;
+use crate::ripple::{RippleParams, MAX_RADIUS};
+
+// Splash settings
+pub const MAX_PARTICLES: usize = 400;       // Droplets alive at once; bursts beyond this are cut short
+pub const PARTICLES_PER_SPLASH: usize = 10; // Droplets thrown by a default sized ripple
+pub const PARTICLE_LIFETIME: f32 = 2.0;     // Seconds before a droplet that never landed is dropped
+pub const GRAVITY: f32 = 900.0;             // Pull back to the surface, in pixels per second squared
+pub const MIN_THROW: f32 = 40.0;            // Slowest outward speed, pixels per second
+pub const MAX_THROW: f32 = 140.0;           // Fastest outward speed
+pub const MIN_LIFT: f32 = 180.0;            // Slowest upward launch speed
+pub const MAX_LIFT: f32 = 360.0;            // Fastest upward launch speed
+pub const MIN_SIZE: f32 = 2.0;              // Smallest droplet diameter
+pub const MAX_SIZE: f32 = 4.5;              // Largest droplet diameter
+pub const HEIGHT_OFFSET: f32 = 0.4;         // Screen shift per pixel of height, fakes the top-down perspective
+pub const SECONDARY_SCALE: f32 = 0.5;       // Size of ripples where droplets land, relative to a droplet ripple
+
+// A droplet thrown up by a splash. It moves across the surface while its
+// height rises and falls under gravity; reaching height 0 means it landed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Particle {
+    pub position: Point,                    // Point on the surface below the droplet
+    pub velocity: Point,                    // Speed across the surface, pixels per second
+    pub height: f32,                        // Height above the surface
+    pub lift: f32,                          // Vertical speed, positive upwards
+    pub age: f32,                           // Seconds since launch
+    pub size: f32,                          // Diameter in pixels
+    pub color: Color,
+}
+
+impl Particle {
+    // Where to draw the droplet: shifted up the screen by its height
+    pub fn draw_position(&self) -> Point {
+        Point::new(self.position.x, self.position.y + self.height * HEIGHT_OFFSET)
+    }
+}
+
+// Pool of splash droplets, thrown when ripples are spawned and turned into
+// small secondary ripples where they land
+#[derive(Clone, Debug)]
+pub struct Splashes {
+    pub enabled: bool,
+    pub particles: Vec<Particle>,           // Live droplets
+    pub cap: usize,                         // Most droplets alive at once
+    pub per_splash: usize,                  // Droplets thrown by a default sized ripple
+    pub lifetime: f32,                      // Seconds before an airborne droplet is dropped
+    pub palette: Palette,                   // Droplet colors, independent of the ring palette
+    picker: ColorPicker,
+}
+
+impl Default for Splashes {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            particles: Vec::new(),
+            cap: MAX_PARTICLES,
+            per_splash: PARTICLES_PER_SPLASH,
+            lifetime: PARTICLE_LIFETIME,
+            palette: spray_palette(),
+            picker: ColorPicker::default(),
+        }
+    }
+}
+
+// Default droplet colors: foam and light water tints
+pub fn spray_palette() -> Palette {
+    Palette::new("spray", vec![
+        Color::new(255, 255, 255),
+        Color::new(224, 255, 255),
+        Color::new(200, 230, 255),
+        Color::new(170, 210, 240),
+    ])
+}
+
+impl Splashes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Throw droplets out of a ripple just spawned at `center`. Bigger
+    // ripples throw more, faster droplets.
+    pub fn burst(&mut self, center: Point, params: &RippleParams, rng: &mut impl Rng) {
+        if !self.enabled {
+            return;
+        }
+        let scale = (params.max_radius / MAX_RADIUS).max(0.0).sqrt();
+        let count = ((self.per_splash as f32 * scale).round() as usize).min(self.cap.saturating_sub(self.particles.len()));
+        for _ in 0..count {
+            let angle = random::range(rng, 0.0, std::f32::consts::TAU);
+            let throw = random::range(rng, MIN_THROW, MAX_THROW) * scale;
+            self.particles.push(Particle {
+                position: center,
+                velocity: Point::new(angle.cos() * throw, angle.sin() * throw),
+                height: 0.0,
+                lift: random::range(rng, MIN_LIFT, MAX_LIFT) * scale.min(1.5),
+                age: 0.0,
+                size: random::range(rng, MIN_SIZE, MAX_SIZE),
+                color: self.picker.pick(&self.palette, rng),
+            });
+        }
+    }
+
+    // Move every droplet by `dt` seconds. Returns the droplets that landed
+    // this step; they and any that outlived `lifetime` leave the pool.
+    pub fn update(&mut self, dt: f32) -> Vec<Particle> {
+        let mut landed = Vec::new();
+        for particle in self.particles.iter_mut() {
+            particle.age += dt;
+            particle.position.x += particle.velocity.x * dt;
+            particle.position.y += particle.velocity.y * dt;
+            particle.lift -= GRAVITY * dt;
+            particle.height += particle.lift * dt;
+            if particle.height <= 0.0 && particle.lift < 0.0 {
+                particle.height = 0.0;
+                landed.push(*particle);
+            }
+        }
+        let lifetime = self.lifetime;
+        // Droplets still at height 0 but on their way up were only just
+        // thrown (e.g. by a burst followed by a zero-length frame)
+        self.particles.retain(|p| (p.height > 0.0 || p.lift > 0.0) && p.age < lifetime);
+        landed
+    }
+
+    // Parameters for the ripple a landed droplet makes, based on the
+    // droplet ripple kind
+    pub fn secondary_params(droplet: RippleParams) -> RippleParams {
+        RippleParams {
+            amount: ((droplet.amount as f32 * SECONDARY_SCALE).round() as i16).max(1),
+            max_radius: droplet.max_radius * SECONDARY_SCALE,
+            initial_radius: droplet.initial_radius * SECONDARY_SCALE,
+            fade_distance: droplet.fade_distance * SECONDARY_SCALE,
+            stroke_weight: (droplet.stroke_weight * SECONDARY_SCALE).max(1.0),
+            ..droplet
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn splashes() -> Splashes {
+        Splashes { enabled: true, ..Splashes::new() }
+    }
+
+    #[test]
+    fn droplets_survive_a_zero_length_frame() {
+        let mut splashes = splashes();
+        splashes.burst(Point::default(), &RippleParams::DEFAULT, &mut random::seeded(1));
+        assert_eq!(splashes.particles.len(), PARTICLES_PER_SPLASH);
+        assert!(splashes.update(0.0).is_empty());
+        assert_eq!(splashes.particles.len(), PARTICLES_PER_SPLASH);
+    }
+
+    #[test]
+    fn every_droplet_lands_once() {
+        let mut splashes = splashes();
+        splashes.burst(Point::default(), &RippleParams::DEFAULT, &mut random::seeded(1));
+        let mut landed = 0;
+        for _ in 0..60 {
+            landed += splashes.update(1.0 / 60.0).len();
+        }
+        assert_eq!(landed, PARTICLES_PER_SPLASH);
+        assert!(splashes.particles.is_empty());
+    }
+
+    #[test]
+    fn bursts_stop_at_the_cap() {
+        let mut splashes = Splashes { cap: 15, ..splashes() };
+        let mut rng = random::seeded(1);
+        splashes.burst(Point::default(), &RippleParams::DEFAULT, &mut rng);
+        splashes.burst(Point::default(), &RippleParams::DEFAULT, &mut rng);
+        assert_eq!(splashes.particles.len(), 15);
+        splashes.enabled = false;
+        splashes.particles.clear();
+        splashes.burst(Point::default(), &RippleParams::DEFAULT, &mut rng);
+        assert!(splashes.particles.is_empty());
+    }
+}
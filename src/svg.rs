@@ -0,0 +1,329 @@
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use crate::field::RippleField;
+use crate::geom::{Bounds, Point};
+use crate::raster::Canvas;
+use crate::reflection::Edges;
+use crate::scene::{self, Primitive, Rgba, RingStyle};
+
+// How backgrounds go into an SVG
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum BackgroundStyle {
+    Gradient,                               // Rows become one vertical gradient, the rest stays vector
+    Raster,                                 // Drawn with the CPU rasterizer and embedded as a PNG
+}
+
+impl BackgroundStyle {
+    pub const ALL: [BackgroundStyle; 2] = [BackgroundStyle::Gradient, BackgroundStyle::Raster];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BackgroundStyle::Gradient => "gradient",
+            BackgroundStyle::Raster => "raster",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<BackgroundStyle> {
+        BackgroundStyle::ALL.iter().copied().find(|style| style.name().eq_ignore_ascii_case(name))
+    }
+}
+
+// An SVG image of the simulation area, built layer by layer. Every layer is
+// a named group, so the parts stay separate in vector editors.
+pub struct SvgDocument {
+    bounds: Bounds,
+    defs: String,
+    body: String,
+    gradients: usize,                       // Gradients defined so far, for unique ids
+}
+
+impl SvgDocument {
+    // An empty document showing `bounds`, on black like the window
+    pub fn new(bounds: Bounds) -> Self {
+        let mut document = Self { bounds, defs: String::new(), body: String::new(), gradients: 0 };
+        let _ = writeln!(document.body, r#"<rect id="backdrop" x="0" y="0" width="{}" height="{}" fill="rgb(0,0,0)"/>"#, bounds.width(), bounds.height());
+        document
+    }
+
+    // The whole frame: background, then every live ripple in its own group,
+    // then splash droplets
+    pub fn frame(
+        field: &RippleField,
+        style: &RingStyle,
+        bounds: Bounds,
+        reflect: Option<Edges>,
+        background: &[Primitive],
+        background_style: BackgroundStyle,
+    ) -> Result<Self, String> {
+        let mut document = Self::new(bounds);
+        match background_style {
+            BackgroundStyle::Gradient => document.background_gradient(background),
+            BackgroundStyle::Raster => document.background_raster(background)?,
+        }
+        document.body.push_str("<g id=\"ripples\">\n");
+        for ripple in &field.ripples {
+            let reflections = match reflect {
+                Some(edges) => ripple.reflections(bounds, edges),
+                None => Vec::new(),
+            };
+            let mut primitives = Vec::new();
+            scene::ripple(&mut primitives, ripple, field, style, &reflections);
+            document.group(&format!("ripple-{}", ripple.serial), &primitives);
+        }
+        document.body.push_str("</g>\n");
+        let mut droplets = Vec::new();
+        scene::particles(&mut droplets, &field.splashes);
+        document.group("splashes", &droplets);
+        Ok(document)
+    }
+
+    // Add primitives as vector shapes in a group called `id`
+    pub fn group(&mut self, id: &str, primitives: &[Primitive]) {
+        if primitives.is_empty() {
+            return;
+        }
+        let _ = writeln!(self.body, r#"<g id="{}">"#, id);
+        for primitive in primitives {
+            self.primitive(primitive);
+        }
+        self.body.push_str("</g>\n");
+    }
+
+    // Add a background with its full-width rows merged into a vertical
+    // gradient. Specks, bubbles and rays stay vector shapes on top.
+    pub fn background_gradient(&mut self, primitives: &[Primitive]) {
+        let (left, right) = (self.bounds.left, self.bounds.right);
+        let is_row = |primitive: &&Primitive| {
+            matches!(primitive, Primitive::Line { start, end, .. } if start.y == end.y && start.x <= left && end.x >= right)
+        };
+        let rows = primitives.iter().filter(is_row).collect::<Vec<_>>();
+        let rest = primitives.iter().filter(|primitive| !is_row(primitive)).cloned().collect::<Vec<_>>();
+        if rows.is_empty() && rest.is_empty() {
+            return;
+        }
+
+        self.body.push_str("<g id=\"background\">\n");
+        if !rows.is_empty() {
+            self.gradients += 1;
+            let id = format!("background-gradient-{}", self.gradients);
+            let _ = writeln!(self.defs, r#"<linearGradient id="{}" x1="0" y1="0" x2="0" y2="1">"#, id);
+            // Stops run top to bottom, each row a color band
+            let mut stops = rows.iter().filter_map(|row| match row {
+                Primitive::Line { start, color, .. } => Some((start.y, *color)),
+                _ => None,
+            }).collect::<Vec<_>>();
+            stops.sort_by(|a, b| b.0.total_cmp(&a.0));
+            for (y, color) in stops {
+                let offset = ((self.bounds.top - y) / self.bounds.height()).clamp(0.0, 1.0);
+                let _ = writeln!(self.defs, r#"  <stop offset="{:.4}" stop-color="{}" stop-opacity="{}"/>"#, offset, rgb(color), opacity(color));
+            }
+            self.defs.push_str("</linearGradient>\n");
+            let _ = writeln!(self.body, r#"<rect x="0" y="0" width="{}" height="{}" fill="url(#{})"/>"#, self.bounds.width(), self.bounds.height(), id);
+        }
+        for primitive in &rest {
+            self.primitive(primitive);
+        }
+        self.body.push_str("</g>\n");
+    }
+
+    // Add a background drawn by the CPU rasterizer and embedded as a PNG.
+    // The link is given as SVG 2 `href` and as `xlink:href` for older readers.
+    pub fn background_raster(&mut self, primitives: &[Primitive]) -> Result<(), String> {
+        if primitives.is_empty() {
+            return Ok(());
+        }
+        let mut canvas = Canvas::new(self.bounds.width().round() as u32, self.bounds.height().round() as u32)?;
+        canvas.draw(primitives);
+        let png = canvas.encode_png()?;
+        let _ = writeln!(
+            self.body,
+            r#"<image id="background" x="0" y="0" width="{}" height="{}" href="{2}" xlink:href="{2}"/>"#,
+            self.bounds.width(),
+            self.bounds.height(),
+            format!("data:image/png;base64,{}", base64(&png))
+        );
+        Ok(())
+    }
+
+    // The finished SVG text
+    pub fn finish(&self) -> String {
+        let (width, height) = (self.bounds.width(), self.bounds.height());
+        let mut text = String::new();
+        text.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let _ = writeln!(
+            text,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+            width, height
+        );
+        if !self.defs.is_empty() {
+            let _ = write!(text, "<defs>\n{}</defs>\n", self.defs);
+        }
+        text.push_str(&self.body);
+        text.push_str("</svg>\n");
+        text
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.finish()).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    fn primitive(&mut self, primitive: &Primitive) {
+        match primitive {
+            Primitive::Rect { center, width, height, color } => {
+                let corner = self.map(Point::new(center.x - width / 2.0, center.y + height / 2.0));
+                let _ = writeln!(self.body, r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" {}/>"#, corner.x, corner.y, width, height, fill(*color));
+            }
+            Primitive::Ellipse { center, width, height, color } => {
+                let center = self.map(*center);
+                let _ = writeln!(self.body, r#"<ellipse cx="{:.2}" cy="{:.2}" rx="{:.2}" ry="{:.2}" {}/>"#, center.x, center.y, width / 2.0, height / 2.0, fill(*color));
+            }
+            Primitive::Ring { center, width, height, weight, color } => {
+                let center = self.map(*center);
+                let _ = writeln!(self.body, r#"<ellipse cx="{:.2}" cy="{:.2}" rx="{:.2}" ry="{:.2}" fill="none" {}/>"#, center.x, center.y, width / 2.0, height / 2.0, stroke(*color, *weight));
+            }
+            Primitive::Line { start, end, weight, color } => self.polyline(&[*start, *end], *color, *weight),
+            Primitive::Polyline { points, weight } => {
+                // A stroke has one color, so a ring of one color is a single
+                // polygon, and one whose color varies along it becomes a
+                // group of runs whose butt ends meet on straight stretches
+                let runs = scene::color_runs(points).into_iter().filter(|run| run.color[3] > 0.0).collect::<Vec<_>>();
+                match runs.as_slice() {
+                    [] => {}
+                    [run] if run.closed => self.polygon(&run.points, run.color, *weight),
+                    runs => {
+                        self.body.push_str("<g class=\"ring\">\n");
+                        for run in runs {
+                            self.polyline(&run.points, run.color, *weight);
+                        }
+                        self.body.push_str("</g>\n");
+                    }
+                }
+            }
+        }
+    }
+
+    fn polygon(&mut self, points: &[Point], color: Rgba, weight: f32) {
+        if color[3] <= 0.0 || points.len() < 3 {
+            return;
+        }
+        let _ = writeln!(self.body, r#"<polygon points="{}" fill="none" stroke-linejoin="round" {}/>"#, self.coordinates(points), stroke(color, weight));
+    }
+
+    fn polyline(&mut self, points: &[Point], color: Rgba, weight: f32) {
+        if color[3] <= 0.0 || points.len() < 2 {
+            return;
+        }
+        let _ = writeln!(self.body, r#"<polyline points="{}" fill="none" stroke-linejoin="round" {}/>"#, self.coordinates(points), stroke(color, weight));
+    }
+
+    // Points as an SVG `points` list
+    fn coordinates(&self, points: &[Point]) -> String {
+        points.iter().map(|&point| {
+            let point = self.map(point);
+            format!("{:.2},{:.2}", point.x, point.y)
+        }).collect::<Vec<_>>().join(" ")
+    }
+
+    // Simulation coordinates (origin at the center, y up) to SVG (top left, y down)
+    fn map(&self, point: Point) -> Point {
+        Point::new(point.x - self.bounds.left, self.bounds.top - point.y)
+    }
+}
+
+fn rgb(color: Rgba) -> String {
+    let [red, green, blue] = [color[0], color[1], color[2]].map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8);
+    format!("rgb({},{},{})", red, green, blue)
+}
+
+fn opacity(color: Rgba) -> String {
+    format!("{:.3}", color[3].clamp(0.0, 1.0))
+}
+
+fn fill(color: Rgba) -> String {
+    format!(r#"fill="{}" fill-opacity="{}""#, rgb(color), opacity(color))
+}
+
+fn stroke(color: Rgba, weight: f32) -> String {
+    format!(r#"stroke="{}" stroke-opacity="{}" stroke-width="{:.2}""#, rgb(color), opacity(color), weight.max(0.0))
+}
+
+// Standard base64 with padding, for embedding images
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let value = chunk.iter().enumerate().fold(0u32, |value, (i, &byte)| value | ((byte as u32) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(ALPHABET[((value >> (18 - 6 * i)) & 63) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring(colors: &[Rgba]) -> Primitive {
+        let points = (0..=8).map(|i| {
+            let angle = i as f32 / 8.0 * std::f32::consts::TAU;
+            (Point::new(10.0 * angle.cos(), 10.0 * angle.sin()), colors[i % colors.len()])
+        }).collect();
+        Primitive::Polyline { points, weight: 2.0 }
+    }
+
+    #[test]
+    fn one_color_rings_are_single_polygons() {
+        let mut document = SvgDocument::new(Bounds::from_size(100.0, 100.0));
+        document.group("ripple", &[ring(&[[1.0, 0.0, 0.0, 1.0]])]);
+        let text = document.finish();
+        assert_eq!(text.matches("<polygon").count(), 1);
+        assert!(!text.contains("<polyline"));
+        assert!(text.contains(r#"points="60.00,50.00 "#));
+    }
+
+    #[test]
+    fn rings_changing_color_are_one_group_of_runs() {
+        let mut document = SvgDocument::new(Bounds::from_size(100.0, 100.0));
+        document.group("ripple", &[ring(&[[1.0, 0.0, 0.0, 1.0], [0.0, 0.0, 1.0, 1.0]])]);
+        let text = document.finish();
+        assert_eq!(text.matches(r#"<g class="ring">"#).count(), 1);
+        assert_eq!(text.matches("<polyline").count(), 8);
+        assert!(!text.contains("stroke-linecap"));
+    }
+
+    #[test]
+    fn runs_meet_halfway_along_a_segment() {
+        let (red, blue) = ([1.0, 0.0, 0.0, 1.0], [0.0, 0.0, 1.0, 1.0]);
+        let points = vec![(Point::new(-10.0, 0.0), red), (Point::new(0.0, 0.0), red), (Point::new(0.0, 10.0), blue)];
+        let mut document = SvgDocument::new(Bounds::from_size(100.0, 100.0));
+        document.group("ripple", &[Primitive::Polyline { points, weight: 2.0 }]);
+        let text = document.finish();
+        assert!(text.contains(r#"<polyline points="40.00,50.00 50.00,50.00 50.00,45.00" "#), "{}", text);
+        assert!(text.contains(r#"<polyline points="50.00,45.00 50.00,40.00" "#), "{}", text);
+    }
+
+    #[test]
+    fn embedded_images_use_both_link_attributes() {
+        let bounds = Bounds::from_size(4.0, 4.0);
+        let mut document = SvgDocument::new(bounds);
+        document.background_raster(&[Primitive::Rect { center: Point::default(), width: 4.0, height: 4.0, color: [0.0, 0.5, 1.0, 1.0] }]).unwrap();
+        let text = document.finish();
+        assert!(text.contains(r#"xmlns:xlink="http://www.w3.org/1999/xlink""#));
+        assert!(text.contains(r#" href="data:image/png;base64,iVBOR"#));
+        assert!(text.contains(r#" xlink:href="data:image/png;base64,iVBOR"#));
+    }
+
+    #[test]
+    fn base64_pads_partial_groups() {
+        assert_eq!(base64(b"Man"), "TWFu");
+        assert_eq!(base64(b"Ma"), "TWE=");
+        assert_eq!(base64(b"M"), "TQ==");
+    }
+}
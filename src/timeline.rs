@@ -0,0 +1,428 @@
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::emitter::Spawn;
+use crate::expr::Expr;
+use crate::field::RippleField;
+use crate::geom::{Bounds, Point};
+use crate::ripple::{RippleKind, RippleParams};
+
+// Parameter overrides a timeline event applies on top of its ripple kind
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Overrides {
+    pub amount: Option<i16>,
+    pub max_radius: Option<f32>,
+    pub radius_speed: Option<f32>,
+    pub initial_radius: Option<f32>,
+    pub ring_spacing: Option<f32>,
+    pub stroke_weight: Option<f32>,
+    pub fade_distance: Option<f32>,
+    pub stretch: Option<f32>,
+    pub direction: Option<f32>,
+}
+
+impl Overrides {
+    pub fn apply(&self, params: RippleParams) -> RippleParams {
+        RippleParams {
+            amount: self.amount.unwrap_or(params.amount),
+            max_radius: self.max_radius.unwrap_or(params.max_radius),
+            radius_speed: self.radius_speed.unwrap_or(params.radius_speed),
+            initial_radius: self.initial_radius.unwrap_or(params.initial_radius),
+            ring_spacing: self.ring_spacing.unwrap_or(params.ring_spacing),
+            stroke_weight: self.stroke_weight.unwrap_or(params.stroke_weight),
+            fade_distance: self.fade_distance.unwrap_or(params.fade_distance),
+            stretch: self.stretch.unwrap_or(params.stretch),
+            direction: self.direction.unwrap_or(params.direction),
+            ..params
+        }
+    }
+}
+
+// A ripple spawned by a cue. Its position is worked out when the cue fires,
+// so expressions can refer to the window size.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CueRipple {
+    pub x: Expr,
+    pub y: Expr,
+    pub kind: RippleKind,
+    pub strength: f32,                      // Scales the kind like a faster drag does
+    pub overrides: Overrides,
+}
+
+// One moment of a timeline: a ripple and/or changes to the scene
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cue {
+    pub time: f32,                          // Seconds from the start of the timeline
+    pub beat: f32,                          // The same moment in beats
+    pub repetition: u32,                    // Which repeat of its event this is, from 0
+    pub ripple: Option<CueRipple>,
+    pub background: Option<String>,         // Background to switch to, by name
+    pub wobble: Option<bool>,
+    pub fade: Option<bool>,
+}
+
+impl Cue {
+    // Where the cue's ripple lands. Expressions see t (seconds), beat, n (the
+    // repetition), w and h (window size) and pi.
+    pub fn position(&self, bounds: Bounds) -> Result<Point, String> {
+        let Some(ripple) = &self.ripple else {
+            return Ok(bounds.center());
+        };
+        let variables = [
+            ("t", self.time),
+            ("beat", self.beat),
+            ("n", self.repetition as f32),
+            ("w", bounds.width()),
+            ("h", bounds.height()),
+        ];
+        let center = bounds.center();
+        Ok(Point::new(center.x + ripple.x.eval(&variables)?, center.y + ripple.y.eval(&variables)?))
+    }
+
+    // Spawn the cue's ripple, if it has one, into `field`
+    pub fn spawn(&self, field: &mut RippleField, bounds: Bounds) -> Result<(), String> {
+        let Some(ripple) = &self.ripple else {
+            return Ok(());
+        };
+        let position = self.position(bounds)?;
+        let spawn = Spawn { strength: ripple.strength, ..Spawn::new(position, ripple.kind) };
+        field.spawn_with(position, ripple.overrides.apply(spawn.params(field.params(ripple.kind))));
+        Ok(())
+    }
+
+    // When the cue's ripple, with the kind's default parameters, has faded
+    // out; scene changes end when they happen
+    pub fn end(&self) -> f32 {
+        let Some(ripple) = &self.ripple else {
+            return self.time;
+        };
+        let spawn = Spawn { strength: ripple.strength, ..Spawn::new(Point::default(), ripple.kind) };
+        self.time + ripple.overrides.apply(spawn.params(ripple.kind.default_params())).lifetime()
+    }
+
+    // Scene changes the cue makes
+    pub fn scene(&self) -> SceneState {
+        SceneState { background: self.background.clone(), wobble: self.wobble, fade: self.fade }
+    }
+}
+
+// Scene settings in effect at some point of a timeline, from the latest
+// cues that set them
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SceneState {
+    pub background: Option<String>,
+    pub wobble: Option<bool>,
+    pub fade: Option<bool>,
+}
+
+// Timeline file layout, before times and expressions are worked out
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TimelineFile {
+    bpm: Option<f32>,
+    #[serde(default, rename = "loop")]
+    looped: bool,
+    length: Option<f32>,                    // Seconds, or beats with `bpm`
+    #[serde(default, rename = "event")]
+    events: Vec<EventEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EventEntry {
+    time: Option<f32>,
+    beat: Option<f32>,
+    repeat: Option<u32>,                    // Number of times the event happens
+    every: Option<f32>,                     // Gap between repeats, in the unit of `time` or `beat`
+    x: Option<Coordinate>,
+    y: Option<Coordinate>,
+    kind: Option<RippleKind>,
+    strength: Option<f32>,
+    #[serde(default)]
+    params: Overrides,
+    background: Option<String>,
+    wobble: Option<bool>,
+    fade: Option<bool>,
+}
+
+// A position given as a plain number or an expression string
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Coordinate {
+    Number(f32),
+    Expression(String),
+}
+
+impl Coordinate {
+    fn compile(coordinate: Option<&Coordinate>) -> Result<Expr, String> {
+        match coordinate {
+            None => Ok(Expr::Number(0.0)),
+            Some(Coordinate::Number(value)) => Ok(Expr::Number(*value)),
+            Some(Coordinate::Expression(text)) => Expr::parse(text),
+        }
+    }
+}
+
+// An authored sequence of cues, played against the application clock
+#[derive(Clone, Debug)]
+pub struct Timeline {
+    pub cues: Vec<Cue>,                     // Sorted by time
+    pub bpm: f32,                           // Tempo beat times were given in
+    pub looped: bool,                       // Start over after `length`
+    pub length: f32,                        // Seconds before looping
+    clock: f32,                             // Seconds into the timeline
+    cursor: usize,                          // Next cue to fire
+    playing: bool,
+    last_time: Option<f32>,                 // Application time at the previous update
+}
+
+impl Timeline {
+    // Whether the timeline is running (rather than paused)
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    // Seconds into the timeline
+    pub fn clock(&self) -> f32 {
+        self.clock
+    }
+
+    pub fn is_finished(&self) -> bool {
+        !self.looped && self.cursor >= self.cues.len()
+    }
+
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    pub fn toggle(&mut self) {
+        self.playing = !self.playing;
+    }
+
+    // Jump to `time` seconds. Cues before it are skipped; use `state_at` to
+    // catch up with the scene settings they would have made.
+    pub fn seek(&mut self, time: f32) {
+        self.clock = if self.looped && self.length > 0.0 { time.rem_euclid(self.length) } else { time.max(0.0) };
+        self.cursor = self.cues.iter().position(|cue| cue.time >= self.clock).unwrap_or(self.cues.len());
+    }
+
+    // Follow the application clock to `now`. Returns the cues that fell due
+    // while playing, in order.
+    pub fn update(&mut self, now: f32) -> Vec<Cue> {
+        let elapsed = self.last_time.map_or(0.0, |last| (now - last).max(0.0));
+        self.last_time = Some(now);
+        let mut due = Vec::new();
+        if !self.playing {
+            return due;
+        }
+        self.clock += elapsed;
+        while let Some(cue) = self.cues.get(self.cursor) && cue.time <= self.clock {
+            due.push(cue.clone());
+            self.cursor += 1;
+        }
+        // Start over once past the end. The new pass fires from the next
+        // update, so its first cues never land in the same frame as the last.
+        if self.looped && self.length > 0.0 && self.clock >= self.length {
+            self.clock -= self.length;
+            self.cursor = 0;
+        }
+        due
+    }
+
+    // Background, wobble and fade set by the latest cues at or before `time`
+    pub fn state_at(&self, time: f32) -> SceneState {
+        let mut state = SceneState::default();
+        for cue in self.cues.iter().take_while(|cue| cue.time <= time) {
+            if cue.background.is_some() {
+                state.background = cue.background.clone();
+            }
+            state.wobble = cue.wobble.or(state.wobble);
+            state.fade = cue.fade.or(state.fade);
+        }
+        state
+    }
+}
+
+// Parse a TOML timeline:
+//   bpm = 120                       # needed for beat times
+//   loop = true
+//   [[event]]
+//   beat = 4                        # or time = 2.0 (seconds)
+//   repeat = 8                      # optional, with `every` in the same unit
+//   every = 0.5
+//   x = "w / 4 * cos(n * pi / 4)"   # offsets from the window center
+//   y = 0
+//   kind = "swell"
+//   params = { max_radius = 300 }
+//   background = "lava"
+//   wobble = true
+pub fn parse(text: &str) -> Result<Timeline, String> {
+    let file: TimelineFile = toml::from_str(text).map_err(|e| e.to_string())?;
+    let bpm = file.bpm.unwrap_or(0.0);
+    let beat_length = if bpm > 0.0 { 60.0 / bpm } else { 0.0 };
+    let to_seconds = |value: f32, in_beats: bool| -> Result<f32, String> {
+        match in_beats {
+            true if bpm <= 0.0 => Err("beat times need a positive bpm".to_string()),
+            true => Ok(value * beat_length),
+            false => Ok(value),
+        }
+    };
+
+    let mut cues = Vec::new();
+    for (number, event) in file.events.iter().enumerate() {
+        let context = |e: String| format!("event {}: {}", number + 1, e);
+        let (start, in_beats) = match (event.time, event.beat) {
+            (Some(time), None) => (time, false),
+            (None, Some(beat)) => (beat, true),
+            _ => return Err(context("needs either a time or a beat".to_string())),
+        };
+        let ripple = if event.x.is_some() || event.y.is_some() || event.kind.is_some() {
+            Some(CueRipple {
+                x: Coordinate::compile(event.x.as_ref()).map_err(context)?,
+                y: Coordinate::compile(event.y.as_ref()).map_err(context)?,
+                kind: event.kind.unwrap_or(RippleKind::Drop),
+                strength: event.strength.unwrap_or(1.0),
+                overrides: event.params,
+            })
+        } else {
+            None
+        };
+        for repetition in 0..event.repeat.unwrap_or(1) {
+            let at = start + repetition as f32 * event.every.unwrap_or(0.0);
+            let time = to_seconds(at, in_beats).map_err(context)?;
+            cues.push(Cue {
+                time,
+                beat: if beat_length > 0.0 { time / beat_length } else { 0.0 },
+                repetition,
+                ripple: ripple.clone(),
+                background: event.background.clone(),
+                wobble: event.wobble,
+                fade: event.fade,
+            });
+        }
+    }
+    cues.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+    let length = match file.length {
+        Some(length) => to_seconds(length, bpm > 0.0)?,
+        // Until the last ripple has faded, so a loop never restarts on top of it
+        None => cues.iter().map(Cue::end).fold(0.0, f32::max),
+    };
+    Ok(Timeline { cues, bpm, looped: file.looped, length, clock: 0.0, cursor: 0, playing: true, last_time: None })
+}
+
+// Load a timeline file in the format accepted by `parse`
+pub fn load(path: &Path) -> Result<Timeline, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHOW: &str = r#"
+        bpm = 120
+        loop = true
+        length = 16
+
+        [[event]]
+        beat = 0
+        repeat = 8
+        every = 0.5
+        x = "w / 4 * cos(n * pi / 4)"
+        y = "h / 4 * sin(n * pi / 4)"
+        kind = "drop"
+        strength = 1.5
+        params = { amount = 3, max_radius = 250 }
+
+        [[event]]
+        beat = 8
+        background = "lava"
+        wobble = true
+        fade = false
+    "#;
+
+    // Times of the cues returned by one update
+    fn fired(timeline: &mut Timeline, now: f32) -> Vec<f32> {
+        timeline.update(now).iter().map(|cue| cue.time).collect()
+    }
+
+    #[test]
+    fn parses_beats_repeats_and_expressions() {
+        let timeline = parse(SHOW).unwrap();
+        assert_eq!(timeline.length, 8.0);
+        assert_eq!(timeline.cues.len(), 9);
+        assert_eq!(timeline.cues[1].time, 0.25);
+        assert_eq!(timeline.cues[1].repetition, 1);
+        assert_eq!(timeline.cues[8].beat, 8.0);
+        assert!(timeline.cues[8].ripple.is_none());
+
+        let bounds = Bounds::from_size(800.0, 600.0);
+        assert_eq!(timeline.cues[0].position(bounds), Ok(Point::new(200.0, 0.0)));
+        let ripple = timeline.cues[0].ripple.as_ref().unwrap();
+        assert_eq!(ripple.kind, RippleKind::Drop);
+        assert_eq!(ripple.overrides.amount, Some(3));
+
+        let mut field = RippleField::new();
+        timeline.cues[0].spawn(&mut field, bounds).unwrap();
+        assert_eq!(field.ripples[0].params.amount, 3);
+        assert_eq!(field.ripples[0].params.max_radius, 250.0);
+    }
+
+    #[test]
+    fn rejects_malformed_events() {
+        assert!(parse("[[event]]\nx = 1").unwrap_err().contains("event 1"));
+        assert!(parse("[[event]]\nbeat = 1\nkind = \"swell\"").unwrap_err().contains("bpm"));
+        assert!(parse("[[event]]\ntime = 1\nx = \"w +\"").is_err());
+        assert!(parse("[[event]]\ntime = 1\ncolour = \"red\"").is_err());
+    }
+
+    #[test]
+    fn implicit_length_waits_for_the_last_ripple_to_fade() {
+        let timeline = parse("[[event]]\ntime = 2\nkind = \"swell\"\n[[event]]\ntime = 3\nwobble = true").unwrap();
+        assert_eq!(timeline.length, 2.0 + RippleKind::Swell.default_params().lifetime());
+        let scene_only = parse("[[event]]\ntime = 3\nwobble = true").unwrap();
+        assert_eq!(scene_only.length, 3.0);
+    }
+
+    #[test]
+    fn updates_fire_cues_in_order_while_playing() {
+        let mut timeline = parse("[[event]]\ntime = 0.5\nrepeat = 4\nevery = 0.5\nkind = \"drop\"").unwrap();
+        assert!(fired(&mut timeline, 10.0).is_empty(), "the first update only starts the clock");
+        assert_eq!(fired(&mut timeline, 11.2), [0.5, 1.0]);
+        timeline.pause();
+        assert!(fired(&mut timeline, 20.0).is_empty());
+        timeline.play();
+        assert_eq!(fired(&mut timeline, 21.0), [1.5, 2.0]);
+        assert!(timeline.is_finished());
+    }
+
+    #[test]
+    fn loops_start_the_next_pass_on_the_following_update() {
+        let mut timeline = parse("loop = true\nlength = 1\n[[event]]\ntime = 0\nkind = \"drop\"").unwrap();
+        assert_eq!(fired(&mut timeline, 0.0), [0.0]);
+        // A long frame crosses the end: the pass wraps once, without firing its start yet
+        assert!(fired(&mut timeline, 2.5).is_empty());
+        assert_eq!(timeline.clock(), 1.5);
+        assert_eq!(fired(&mut timeline, 2.6), [0.0]);
+    }
+
+    #[test]
+    fn seeking_keeps_the_scene_of_skipped_cues() {
+        let mut timeline = parse(SHOW).unwrap();
+        timeline.seek(9.0);
+        assert_eq!(timeline.clock(), 1.0);
+        let state = timeline.state_at(5.0);
+        assert_eq!(state.background.as_deref(), Some("lava"));
+        assert_eq!((state.wobble, state.fade), (Some(true), Some(false)));
+        assert_eq!(timeline.state_at(3.0), SceneState::default());
+    }
+}
@@ -1,114 +1,410 @@
 use nannou::prelude::*;
+use waves::settings::next_value;
+use waves::{BackgroundType, ColorMode, DropSize, Easing, MenuButton, RippleField, SceneSettings, Throttle, WobbleMode};
+
+// Menu layout
+const PADDING: f32 = 10.0;
+const TOGGLE_BUTTON_SIZE: f32 = 30.0;
+const BUTTON_SPACING: f32 = 10.0;
+const BUTTON_WIDTH: f32 = 120.0;
+const BUTTON_HEIGHT: f32 = 25.0;
+const LIST_ROW_HEIGHT: f32 = 18.0;
+const LIST_WIDTH: f32 = 190.0;
+const PREVIEW_WIDTH: f32 = 26.0;        // Easing curve preview drawn inside its button
+
+// Choices cycled through by the wave simulation buttons
+pub const GRID_CELL_SIZES: [f32; 5] = [4.0, 6.0, 8.0, 12.0, 16.0];   // Pixels between wave grid nodes
+pub const DAMPING_LEVELS: [f32; 5] = [0.1, 0.3, 0.6, 1.2, 2.5];      // Wave decay rate per second
+
+// Seconds without input before attract mode starts
+pub const IDLE_TIMEOUTS: [f32; 5] = [15.0, 30.0, 60.0, 120.0, 300.0];
+
+// Rain choices, cycled by the menu and stepped by the arrow keys
+pub const RAIN_RATES: [f32; 6] = [1.0, 2.0, 4.0, 8.0, 16.0, 32.0];  // Drops per second
+pub const WIND_LEVELS: [f32; 5] = [-1.0, -0.5, 0.0, 0.5, 1.0];      // Negative blows left, positive right
+
+// Spacing options for ripples spawned while dragging
+pub const THROTTLE_PRESETS: [Throttle; 4] = [
+    Throttle::Time { interval: 0.1 },
+    Throttle::Distance { spacing: 10.0 },
+    Throttle::Distance { spacing: 25.0 },
+    Throttle::Distance { spacing: 50.0 },
+];
 
 // UI state
+#[derive(Clone)]
 pub struct Menu {
     pub visible: bool,
     pub toggle_button_rect: Rect,
-    pub wobble_enabled: bool,
-    pub fade_enabled: bool,
-    pub wobble_button_rect: Rect,
-    pub fade_button_rect: Rect,
-    pub background_type: BackgroundType,
-    pub bg_type_button_rect: Rect,
+    pub scene: SceneSettings,               // How frames look, shared with the headless renderer
+    pub mode: SimulationMode,
+    pub grid_cell_size: f32,
+    pub damping: f32,
+    pub selected_obstacle: Option<usize>,   // Obstacle targeted by the move and delete buttons
+    pub moving_obstacle: bool,              // Next click in the scene moves the selected obstacle
+    pub velocity_enabled: bool,             // Drag speed scales ripple strength
+    pub stretch_enabled: bool,              // Drag direction stretches ripples into ellipses
+    pub throttle: Throttle,                 // Time or distance spacing of dragged ripples
+    pub rain_enabled: bool,                 // Spawn ripples at random on a timer
+    pub rain_rate: f32,                     // Average drops per second
+    pub drop_size: DropSize,                // Distribution of raindrop sizes
+    pub wind: f32,                          // Pushes raindrops towards one side
+    pub attract_enabled: bool,              // Run scripted patterns when nobody is around
+    pub idle_timeout: f32,                  // Seconds without input before attract mode starts
+    buttons: Vec<(MenuButton, Rect)>,   // Buttons shown when the menu is open, in layout order
 }
 
+// How clicks are turned into waves
 #[derive(PartialEq, Debug, Clone, Copy)]
-pub enum BackgroundType {
-    None,
-    Water,
-    Lava,
-    Radial,
+pub enum SimulationMode {
+    Rings,      // Independent expanding circles
+    Waves,      // Height field solving the damped wave equation
+}
+
+// Move `steps` entries up or down `values` from `current`, stopping at the ends
+pub fn step_value(values: &[f32], current: f32, steps: isize) -> f32 {
+    let index = values.iter().position(|&v| v == current).unwrap_or(0) as isize;
+    values[(index + steps).clamp(0, values.len() as isize - 1) as usize]
+}
+
+fn on_off(enabled: bool) -> &'static str {
+    if enabled { "ON" } else { "OFF" }
+}
+
+fn toggle_color(enabled: bool) -> Rgba {
+    if enabled {
+        rgba(0.2, 0.8, 0.3, 0.9)
+    } else {
+        rgba(0.8, 0.2, 0.2, 0.9)
+    }
 }
 
 impl Menu {
     pub fn new(window_rect: Rect, wobble_enabled: bool, fade_enabled: bool) -> Self {
         // Position in top left corner with some padding
-        let padding = 10.0;
-        let button_size = 30.0;
         let toggle_button_rect = Rect::from_x_y_w_h(
-            window_rect.left() + padding + button_size/2.0,
-            window_rect.top() - padding - button_size/2.0,
-            button_size,
-            button_size,
-        );
-        
-        // Define the other button positions (initially hidden)
-        let button_spacing = 10.0;
-        let button_width = 120.0;
-        let button_height = 25.0;
-        
-        let wobble_button_rect = Rect::from_x_y_w_h(
-            window_rect.left() + padding + button_width/2.0,
-            toggle_button_rect.bottom() - button_spacing - button_height/2.0,
-            button_width,
-            button_height,
-        );
-        
-        let fade_button_rect = Rect::from_x_y_w_h(
-            window_rect.left() + padding + button_width/2.0,
-            wobble_button_rect.bottom() - button_spacing - button_height/2.0,
-            button_width,
-            button_height,
+            window_rect.left() + PADDING + TOGGLE_BUTTON_SIZE/2.0,
+            window_rect.top() - PADDING - TOGGLE_BUTTON_SIZE/2.0,
+            TOGGLE_BUTTON_SIZE,
+            TOGGLE_BUTTON_SIZE,
         );
 
-        let bg_type_button_rect = Rect::from_x_y_w_h(
-            window_rect.left() + padding + button_width/2.0,
-            fade_button_rect.bottom() - button_spacing - button_height/2.0,
-            button_width,
-            button_height,
-        );
-        
+        // Stack the other buttons (initially hidden) below the toggle,
+        // starting a new column whenever one runs off the bottom of the window
+        let mut buttons = Vec::new();
+        let mut column_left = window_rect.left() + PADDING;
+        let mut y = toggle_button_rect.bottom() - BUTTON_SPACING - BUTTON_HEIGHT/2.0;
+        for button in MenuButton::ALL {
+            if y - BUTTON_HEIGHT/2.0 < window_rect.bottom() + PADDING {
+                column_left += BUTTON_WIDTH + BUTTON_SPACING;
+                y = toggle_button_rect.bottom() - BUTTON_SPACING - BUTTON_HEIGHT/2.0;
+            }
+            let rect = Rect::from_x_y_w_h(
+                column_left + BUTTON_WIDTH/2.0,
+                y,
+                BUTTON_WIDTH,
+                BUTTON_HEIGHT,
+            );
+            buttons.push((button, rect));
+            y -= BUTTON_HEIGHT + BUTTON_SPACING;
+        }
+
         Menu {
             visible: false,
             toggle_button_rect,
-            wobble_enabled,      // Use provided parameter
-            fade_enabled,        // Use provided parameter
-            wobble_button_rect,
-            fade_button_rect,
-            background_type: BackgroundType::Water,
-            bg_type_button_rect,
+            scene: SceneSettings::new(wobble_enabled, fade_enabled),
+            mode: SimulationMode::Rings,
+            grid_cell_size: 8.0,
+            damping: 0.6,
+            selected_obstacle: None,
+            moving_obstacle: false,
+            velocity_enabled: true,
+            stretch_enabled: false,
+            throttle: THROTTLE_PRESETS[0],
+            rain_enabled: false,
+            rain_rate: RAIN_RATES[2],
+            drop_size: DropSize::default(),
+            wind: 0.0,
+            attract_enabled: true,
+            idle_timeout: waves::attract::IDLE_TIMEOUT,
+            buttons,
         }
     }
-    
+
     // Check if a point is inside the toggle button
     pub fn is_in_toggle_button(&self, point: Point2) -> bool {
         self.toggle_button_rect.contains(point)
     }
-    
-    // Check if a point is inside the wobble button
-    pub fn is_in_wobble_button(&self, point: Point2) -> bool {
-        self.visible && self.wobble_button_rect.contains(point)
+
+    // Find the menu button under a point, if the menu is open
+    pub fn button_at(&self, point: Point2) -> Option<MenuButton> {
+        if !self.visible {
+            return None;
+        }
+        self.buttons.iter()
+            .find(|(_, rect)| rect.contains(point))
+            .map(|&(button, _)| button)
+    }
+
+    // Check if a point is over any part of the menu
+    pub fn contains(&self, point: Point2, obstacle_count: usize) -> bool {
+        self.is_in_toggle_button(point)
+            || self.button_at(point).is_some()
+            || self.list_row_at(point, obstacle_count).is_some()
+    }
+
+    // Rectangle of one row in the obstacle list, shown right of the buttons
+    fn list_row_rect(&self, index: usize) -> Rect {
+        let right = self.buttons.iter().fold(self.toggle_button_rect.right(), |r, (_, rect)| r.max(rect.right()));
+        Rect::from_x_y_w_h(
+            right + BUTTON_SPACING * 2.0 + LIST_WIDTH/2.0,
+            self.toggle_button_rect.top() - LIST_ROW_HEIGHT * (index as f32 + 0.5),
+            LIST_WIDTH,
+            LIST_ROW_HEIGHT,
+        )
+    }
+
+    // Find the obstacle list row under a point, if the menu is open
+    pub fn list_row_at(&self, point: Point2, obstacle_count: usize) -> Option<usize> {
+        if !self.visible {
+            return None;
+        }
+        (0..obstacle_count).find(|&i| self.list_row_rect(i).contains(point))
+    }
+
+    // Take over the settings of `saved`, keeping this menu's layout, visibility
+    // and obstacle selection
+    pub fn restore_settings(&mut self, saved: &Menu) {
+        *self = Menu {
+            visible: self.visible,
+            toggle_button_rect: self.toggle_button_rect,
+            selected_obstacle: self.selected_obstacle,
+            moving_obstacle: self.moving_obstacle,
+            buttons: std::mem::take(&mut self.buttons),
+            ..saved.clone()
+        };
+    }
+
+    // Apply the effect of clicking a menu button that changes neither the
+    // scene nor the simulation. The scene buttons go to `scene`, and the
+    // obstacle and seed buttons are handled by the front end.
+    pub fn press(&mut self, button: MenuButton) {
+        match button {
+            MenuButton::Rain => self.rain_enabled = !self.rain_enabled,
+            MenuButton::RainRate => self.rain_rate = next_value(&RAIN_RATES, self.rain_rate),
+            MenuButton::DropSize => self.drop_size = self.drop_size.next(),
+            MenuButton::Wind => self.wind = next_value(&WIND_LEVELS, self.wind),
+            MenuButton::Attract => self.attract_enabled = !self.attract_enabled,
+            MenuButton::IdleTimeout => self.idle_timeout = next_value(&IDLE_TIMEOUTS, self.idle_timeout),
+            MenuButton::Mode => {
+                self.mode = match self.mode {
+                    SimulationMode::Rings => SimulationMode::Waves,
+                    SimulationMode::Waves => SimulationMode::Rings,
+                };
+            }
+            MenuButton::GridResolution => {
+                self.grid_cell_size = next_value(&GRID_CELL_SIZES, self.grid_cell_size);
+            }
+            MenuButton::Damping => {
+                self.damping = next_value(&DAMPING_LEVELS, self.damping);
+            }
+            MenuButton::Velocity => self.velocity_enabled = !self.velocity_enabled,
+            MenuButton::Stretch => self.stretch_enabled = !self.stretch_enabled,
+            MenuButton::Spacing => {
+                let index = THROTTLE_PRESETS.iter().position(|&t| t == self.throttle).unwrap_or(0);
+                self.throttle = THROTTLE_PRESETS[(index + 1) % THROTTLE_PRESETS.len()];
+            }
+            _ => {}
+        }
+    }
+
+    // Fill color of a button, reflecting its current state
+    fn button_color(&self, button: MenuButton, field: &RippleField) -> Rgba {
+        match button {
+            MenuButton::Wobble => toggle_color(self.scene.wobble_enabled),
+            MenuButton::WobbleMode => match self.scene.wobble.mode {
+                WobbleMode::Sine => rgba(0.3, 0.3, 0.4, 0.9),
+                WobbleMode::Noise => rgba(0.2, 0.45, 0.45, 0.9),
+            },
+            MenuButton::WobbleAmplitude | MenuButton::WobbleFrequency | MenuButton::WobbleSpeed => rgba(0.3, 0.3, 0.4, 0.9),
+            MenuButton::Fade => toggle_color(self.scene.fade_enabled),
+            MenuButton::Colors => match self.scene.color_mode {
+                ColorMode::Fixed => rgba(0.3, 0.3, 0.4, 0.9),
+                ColorMode::Lifetime => rgba(0.5, 0.35, 0.2, 0.9),
+                ColorMode::Rainbow => rgba(0.6, 0.2, 0.5, 0.9),
+            },
+            MenuButton::Blend | MenuButton::HueModel | MenuButton::Saturation
+            | MenuButton::Lightness | MenuButton::HueSpeed => rgba(0.3, 0.3, 0.4, 0.9),
+            MenuButton::Palette => rgba(0.45, 0.3, 0.35, 0.9),
+            MenuButton::ColorPick => rgba(0.3, 0.3, 0.4, 0.9),
+            MenuButton::Background => match self.scene.background_type {
+                BackgroundType::None => rgba(0.8, 0.2, 0.2, 0.9),
+                BackgroundType::Water => rgba(0.0, 0.4, 0.8, 0.9),
+                BackgroundType::Lava => rgba(0.9, 0.3, 0.0, 0.9),
+                BackgroundType::Radial => rgba(0.8, 0.4, 0.8, 0.9),
+            },
+            MenuButton::Trails => toggle_color(self.scene.trails_enabled),
+            MenuButton::TrailPersistence => rgba(0.3, 0.3, 0.4, 0.9),
+            MenuButton::Splash => toggle_color(field.splashes.enabled),
+            MenuButton::SplashPalette => rgba(0.3, 0.3, 0.4, 0.9),
+            MenuButton::Rain => toggle_color(self.rain_enabled),
+            MenuButton::RainRate | MenuButton::DropSize | MenuButton::Wind => rgba(0.3, 0.3, 0.4, 0.9),
+            MenuButton::Attract => toggle_color(self.attract_enabled),
+            MenuButton::IdleTimeout => rgba(0.3, 0.3, 0.4, 0.9),
+            MenuButton::Mode => match self.mode {
+                SimulationMode::Rings => rgba(0.3, 0.3, 0.7, 0.9),
+                SimulationMode::Waves => rgba(0.0, 0.5, 0.6, 0.9),
+            },
+            MenuButton::GridResolution | MenuButton::Damping => rgba(0.3, 0.3, 0.4, 0.9),
+            MenuButton::Reflect => toggle_color(self.scene.reflect_enabled),
+            // Edge toggles are dimmed while reflections as a whole are off
+            MenuButton::ReflectLeft => self.edge_color(self.scene.reflect_edges.left),
+            MenuButton::ReflectRight => self.edge_color(self.scene.reflect_edges.right),
+            MenuButton::ReflectTop => self.edge_color(self.scene.reflect_edges.top),
+            MenuButton::ReflectBottom => self.edge_color(self.scene.reflect_edges.bottom),
+            MenuButton::ObstacleSelect => rgba(0.3, 0.3, 0.4, 0.9),
+            MenuButton::ObstacleMove => if self.moving_obstacle { rgba(0.8, 0.7, 0.1, 0.9) } else { rgba(0.3, 0.3, 0.4, 0.9) },
+            MenuButton::ObstacleDelete => rgba(0.6, 0.2, 0.2, 0.9),
+            MenuButton::EditKind => rgba(0.4, 0.3, 0.5, 0.9),
+            MenuButton::RadiusEasing | MenuButton::AlphaEasing => rgba(0.3, 0.3, 0.4, 0.9),
+            MenuButton::Velocity => toggle_color(self.velocity_enabled),
+            MenuButton::Stretch => toggle_color(self.stretch_enabled),
+            MenuButton::Spacing => rgba(0.3, 0.3, 0.4, 0.9),
+            MenuButton::Shape => rgba(0.35, 0.3, 0.45, 0.9),
+            MenuButton::Seed => rgba(0.25, 0.25, 0.3, 0.9),
+        }
+    }
+
+    fn edge_color(&self, enabled: bool) -> Rgba {
+        let color = toggle_color(enabled);
+        if self.scene.reflect_enabled { color } else { rgba(color.red * 0.5, color.green * 0.5, color.blue * 0.5, color.alpha) }
     }
 
-    pub fn is_in_bg_type_button(&self, point: Point2) -> bool {
-        self.visible && self.bg_type_button_rect.contains(point)
+    // Label of a button, reflecting its current state
+    fn button_text(&self, button: MenuButton, field: &RippleField) -> String {
+        let obstacle_count = field.obstacles.len();
+        let params = field.params(self.scene.edited_kind);
+        match button {
+            MenuButton::Wobble => if self.scene.wobble_enabled { "Wobble: ON" } else { "Wobble: OFF" }.to_string(),
+            MenuButton::WobbleMode => format!("  Style: {}", self.scene.wobble.mode.name()),
+            MenuButton::WobbleAmplitude => format!("  Amount: {}%", self.scene.wobble.amplitude),
+            MenuButton::WobbleFrequency => format!("  Detail: {}", self.scene.wobble.frequency),
+            MenuButton::WobbleSpeed => format!("  Speed: {}", self.scene.wobble.speed),
+            MenuButton::Fade => if self.scene.fade_enabled { "Fade: ON" } else { "Fade: OFF" }.to_string(),
+            MenuButton::Colors => match self.scene.color_mode {
+                ColorMode::Fixed => "Colors: FIXED",
+                ColorMode::Lifetime => "Colors: LIFETIME",
+                ColorMode::Rainbow => "Colors: RAINBOW",
+            }.to_string(),
+            MenuButton::Blend => format!("  Blend: {}", self.scene.interpolation.name()),
+            MenuButton::HueModel => format!("  Hue: {}", self.scene.rainbow.model.name()),
+            MenuButton::Saturation => format!("  Sat: {}", self.scene.rainbow.saturation),
+            MenuButton::Lightness => format!("  Light: {}", self.scene.rainbow.lightness),
+            MenuButton::HueSpeed => format!("  Cycle: {}/s", self.scene.rainbow.speed),
+            MenuButton::Palette => format!("Palette: {}", field.palettes.active().name),
+            MenuButton::ColorPick => format!("  Pick: {}", field.picker.selection.name()),
+            MenuButton::Background => match self.scene.background_type {
+                BackgroundType::None => "BG: OFF",
+                BackgroundType::Water => "BG: WATER",
+                BackgroundType::Lava => "BG: LAVA",
+                BackgroundType::Radial => "BG: RADIAL",
+            }.to_string(),
+            MenuButton::Trails => format!("Trails: {}", on_off(self.scene.trails_enabled)),
+            MenuButton::TrailPersistence => format!("  Decay: {}s", self.scene.trail_persistence),
+            MenuButton::Splash => format!("Splash: {}", on_off(field.splashes.enabled)),
+            MenuButton::SplashPalette => format!("  Spray: {}", field.splashes.palette.name),
+            MenuButton::Rain => format!("Rain: {}", on_off(self.rain_enabled)),
+            MenuButton::RainRate => format!("  Rate: {}/s", self.rain_rate),
+            MenuButton::DropSize => format!("  Drops: {}", self.drop_size.name()),
+            MenuButton::Wind => format!("  Wind: {}", self.wind),
+            MenuButton::Attract => format!("Attract: {}", on_off(self.attract_enabled)),
+            MenuButton::IdleTimeout => format!("  Idle: {}s", self.idle_timeout),
+            MenuButton::Mode => match self.mode {
+                SimulationMode::Rings => "Mode: RINGS",
+                SimulationMode::Waves => "Mode: WAVES",
+            }.to_string(),
+            MenuButton::GridResolution => format!("Grid: {}px", self.grid_cell_size),
+            MenuButton::Damping => format!("Damping: {}", self.damping),
+            MenuButton::Reflect => if self.scene.reflect_enabled { "Reflect: ON" } else { "Reflect: OFF" }.to_string(),
+            MenuButton::ReflectLeft => format!("  Left: {}", on_off(self.scene.reflect_edges.left)),
+            MenuButton::ReflectRight => format!("  Right: {}", on_off(self.scene.reflect_edges.right)),
+            MenuButton::ReflectTop => format!("  Top: {}", on_off(self.scene.reflect_edges.top)),
+            MenuButton::ReflectBottom => format!("  Bottom: {}", on_off(self.scene.reflect_edges.bottom)),
+            MenuButton::ObstacleSelect => match (self.selected_obstacle, obstacle_count) {
+                (_, 0) => "Obstacles: none".to_string(),
+                (Some(i), n) => format!("Obstacle {}/{}", i + 1, n),
+                (None, n) => format!("Obstacles: {}", n),
+            },
+            MenuButton::ObstacleMove => if self.moving_obstacle { "Click to place" } else { "Move" }.to_string(),
+            MenuButton::ObstacleDelete => "Delete".to_string(),
+            MenuButton::EditKind => format!("Kind: {}", self.scene.edited_kind.name().to_uppercase()),
+            MenuButton::RadiusEasing => format!("Grow: {}", params.radius_easing.name()),
+            MenuButton::AlphaEasing => format!("Alpha: {}", params.alpha_easing.name()),
+            MenuButton::Velocity => format!("Velocity: {}", on_off(self.velocity_enabled)),
+            MenuButton::Stretch => format!("Stretch: {}", on_off(self.stretch_enabled)),
+            MenuButton::Spacing => match self.throttle {
+                Throttle::Time { interval } => format!("Every {}s", interval),
+                Throttle::Distance { spacing } => format!("Every {}px", spacing),
+            },
+            MenuButton::Shape => format!("Shape: {}", field.shape.name()),
+            // Clicking restarts the run from this seed
+            MenuButton::Seed => format!("Seed: {}", field.seed()),
+        }
     }
-    
-    // Check if a point is inside the fade button
-    pub fn is_in_fade_button(&self, point: Point2) -> bool {
-        self.visible && self.fade_button_rect.contains(point)
+
+    // Curve shown in a button's preview box, if it has one
+    fn button_preview(&self, button: MenuButton, field: &RippleField) -> Option<Easing> {
+        let params = field.params(self.scene.edited_kind);
+        match button {
+            MenuButton::RadiusEasing => Some(params.radius_easing),
+            MenuButton::AlphaEasing => Some(params.alpha_easing),
+            _ => None,
+        }
+    }
+
+    // Plot an easing curve in a small box at the right end of a button
+    fn draw_preview(&self, draw: &Draw, rect: Rect, easing: Easing, falling: bool) {
+        let size = rect.h() - 6.0;
+        let left = rect.right() - PREVIEW_WIDTH + (PREVIEW_WIDTH - size) / 2.0 - 2.0;
+        let bottom = rect.bottom() + 3.0;
+        draw.rect()
+            .x_y(left + size / 2.0, bottom + size / 2.0)
+            .w_h(size, size)
+            .color(rgba(0.0, 0.0, 0.0, 0.4));
+
+        let points = (0..=16).map(|i| {
+            let t = i as f32 / 16.0;
+            let value = easing.apply(t);
+            let value = if falling { 1.0 - value } else { value };
+            pt2(left + t * size, bottom + value.clamp(-0.2, 1.2) * size)
+        });
+        draw.polyline()
+            .weight(1.5)
+            .points(points)
+            .color(rgba(1.0, 0.9, 0.4, 1.0));
     }
-    
+
     // Draw the menu
-    pub fn draw(&self, draw: &Draw) {
+    pub fn draw(&self, draw: &Draw, field: &RippleField) {
+        let obstacles = &field.obstacles;
         // Always draw the toggle button
         draw.rect()
             .xy(self.toggle_button_rect.xy())
             .wh(self.toggle_button_rect.wh())
             .color(rgba(0.1, 0.1, 0.2, 0.8));
-            
+
         // Draw the icon (hamburger menu)
         let line_width = self.toggle_button_rect.w() * 0.6;
         let line_height = 2.0;
         let line_spacing = 4.0;
-        
+
         // Define y-positions for the three lines
         let y_positions = [
             self.toggle_button_rect.y() + line_spacing,  // top line
             self.toggle_button_rect.y(),                 // middle line
             self.toggle_button_rect.y() - line_spacing,  // bottom line
         ];
-        
+
         // Draw hamburger menu lines using iteration
         for &y in &y_positions {
             draw.line()
@@ -117,90 +413,73 @@ impl Menu {
                 .weight(line_height)
                 .color(WHITE);
         }
-        
+
         // Draw the rest of the menu if visible
         if self.visible {
-            // Background panel for menu
+            // Background panel covering the toggle and every button column
             let padding = 5.0;
+            let (left, right, bottom, top) = self.buttons.iter().fold(
+                (
+                    self.toggle_button_rect.left(),
+                    self.toggle_button_rect.right(),
+                    self.toggle_button_rect.bottom(),
+                    self.toggle_button_rect.top(),
+                ),
+                |(l, r, b, t), (_, rect)| (l.min(rect.left()), r.max(rect.right()), b.min(rect.bottom()), t.max(rect.top())),
+            );
             let panel_rect = Rect::from_x_y_w_h(
-                self.toggle_button_rect.x(),
-                (self.toggle_button_rect.bottom() + self.bg_type_button_rect.bottom()) / 2.0,
-                self.wobble_button_rect.w() + padding * 2.0,
-                self.toggle_button_rect.bottom() - self.bg_type_button_rect.bottom() + padding * 2.0,
+                (left + right) / 2.0,
+                (bottom + top) / 2.0,
+                right - left + padding * 2.0,
+                top - bottom + padding * 2.0,
             );
-            
+
             draw.rect()
                 .xy(panel_rect.xy())
                 .wh(panel_rect.wh())
                 .color(rgba(0.05, 0.05, 0.1, 0.8));
-                
-            // Wobble toggle button
-            let wobble_color = if self.wobble_enabled { 
-                rgba(0.2, 0.8, 0.3, 0.9) 
-            } else { 
-                rgba(0.8, 0.2, 0.2, 0.9) 
-            };
-            
-            draw.rect()
-                .xy(self.wobble_button_rect.xy())
-                .wh(self.wobble_button_rect.wh())
-                .color(wobble_color);
-                
-            // Wobble button text
-            let wobble_text = if self.wobble_enabled { "Wobble: ON" } else { "Wobble: OFF" };
-            draw.text(wobble_text)
-                .xy(self.wobble_button_rect.xy())
-                .font_size(14)
-                .color(WHITE)
-                .align_text_middle_y();
-                
-            // Fade toggle button
-            let fade_color = if self.fade_enabled { 
-                rgba(0.2, 0.8, 0.3, 0.9) 
-            } else { 
-                rgba(0.8, 0.2, 0.2, 0.9) 
-            };
-            
-            draw.rect()
-                .xy(self.fade_button_rect.xy())
-                .wh(self.fade_button_rect.wh())
-                .color(fade_color);
-                
-            // Fade button text
-            let fade_text = if self.fade_enabled { "Fade: ON" } else { "Fade: OFF" };
-            draw.text(fade_text)
-                .xy(self.fade_button_rect.xy())
-                .font_size(14)
-                .color(WHITE)
-                .align_text_middle_y();
-                 
-
-            // Background type button
-            let bg_color = match self.background_type {
-                BackgroundType::None => rgba(0.8, 0.2, 0.2, 0.9),
-                BackgroundType::Water => rgba(0.0, 0.4, 0.8, 0.9),
-                BackgroundType::Lava => rgba(0.9, 0.3, 0.0, 0.9),
-                BackgroundType::Radial => rgba(0.8, 0.4, 0.8, 0.9),
-            };
-            
-            draw.rect()
-            .xy(self.bg_type_button_rect.xy())
-            .wh(self.bg_type_button_rect.wh())
-            .color(bg_color);
-                
-            // Background type text
-            let bg_text = match self.background_type {
-                BackgroundType::None => "BG: OFF",
-                BackgroundType::Water => "BG: WATER",
-                BackgroundType::Lava => "BG: LAVA",
-                BackgroundType::Radial => "BG: RADIAL",
-            };
-            
-            draw.text(bg_text)
-                .xy(self.bg_type_button_rect.xy())
-                .font_size(14)
-                .color(WHITE)
-                .align_text_middle_y();
+
+            for &(button, rect) in &self.buttons {
+                draw.rect()
+                    .xy(rect.xy())
+                    .wh(rect.wh())
+                    .color(self.button_color(button, field));
+
+                // Buttons with a curve preview keep their label clear of it
+                let preview = self.button_preview(button, field);
+                let text_rect = if preview.is_some() {
+                    Rect::from_x_y_w_h(rect.x() - PREVIEW_WIDTH / 2.0, rect.y(), rect.w() - PREVIEW_WIDTH, rect.h())
+                } else {
+                    rect
+                };
+                draw.text(&self.button_text(button, field))
+                    .xy(text_rect.xy())
+                    .font_size(14)
+                    .color(WHITE)
+                    .align_text_middle_y();
+
+                // Fade curves are plotted as opacity dropping from 1 to 0
+                if let Some(easing) = preview {
+                    self.draw_preview(draw, rect, easing, button == MenuButton::AlphaEasing);
+                }
+            }
+
+            // List the obstacles, highlighting the selected one
+            for (i, obstacle) in obstacles.iter().enumerate() {
+                let rect = self.list_row_rect(i);
+                let selected = self.selected_obstacle == Some(i);
+                draw.rect()
+                    .xy(rect.xy())
+                    .wh(rect.wh())
+                    .color(if selected { rgba(0.5, 0.45, 0.1, 0.9) } else { rgba(0.05, 0.05, 0.1, 0.8) });
+                draw.text(&format!("{}. {}", i + 1, obstacle.describe()))
+                    .xy(rect.xy())
+                    .wh(rect.wh())
+                    .font_size(12)
+                    .color(WHITE)
+                    .left_justify()
+                    .align_text_middle_y();
+            }
         }
     }
-}
\ No newline at end of file
+}
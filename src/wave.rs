@@ -0,0 +1,239 @@
+use crate::geom::{Bounds, Point};
+use crate::obstacle::Obstacle;
+
+// Wave solver settings
+pub const SIM_STEP: f32 = 1.0 / 60.0;       // Fixed solver step in seconds
+pub const COURANT: f32 = 0.5;               // Cells a wave front travels per step (must stay below 0.7 for stability)
+pub const MAX_STEPS_PER_UPDATE: usize = 8;  // Cap on catch-up steps after a slow frame
+pub const IMPULSE_RADIUS: f32 = 12.0;       // Radius of a click impulse, in pixels
+pub const IMPULSE_STRENGTH: f32 = 6.0;      // Height added at the center of a click impulse
+
+// Height field solving the 2D damped wave equation on a regular grid.
+// Nodes are spaced `cell_size` pixels apart across `bounds`; the outermost
+// nodes are held at zero so waves reflect off the edges. Nodes covered by
+// obstacles are held at zero too, so waves reflect and diffract around them.
+#[derive(Clone, Debug)]
+pub struct WaveGrid {
+    bounds: Bounds,                         // Area covered by the grid
+    cell_size: f32,                         // Distance between nodes in pixels
+    cols: usize,                            // Number of nodes across
+    rows: usize,                            // Number of nodes down
+    pub damping: f32,                       // Exponential decay rate per second
+    current: Vec<f32>,                      // Heights at the current step
+    previous: Vec<f32>,                     // Heights at the previous step
+    solid: Vec<bool>,                       // Nodes covered by an obstacle
+    accumulator: f32,                       // Unsimulated time carried between updates
+}
+
+impl WaveGrid {
+    pub fn new(bounds: Bounds, cell_size: f32, damping: f32) -> Self {
+        let cell_size = cell_size.max(1.0);
+        let cols = (bounds.width() / cell_size).ceil() as usize + 1;
+        let rows = (bounds.height() / cell_size).ceil() as usize + 1;
+        let cols = cols.max(3);
+        let rows = rows.max(3);
+
+        Self {
+            bounds,
+            cell_size,
+            cols,
+            rows,
+            damping,
+            current: vec![0.0; cols * rows],
+            previous: vec![0.0; cols * rows],
+            solid: vec![false; cols * rows],
+            accumulator: 0.0,
+        }
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        self.bounds
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    // Height of the surface at a node
+    pub fn height(&self, col: usize, row: usize) -> f32 {
+        self.current[row * self.cols + col]
+    }
+
+    // World position of a node
+    pub fn node_position(&self, col: usize, row: usize) -> Point {
+        Point::new(
+            self.bounds.left + col as f32 * self.cell_size,
+            self.bounds.bottom + row as f32 * self.cell_size,
+        )
+    }
+
+    // Surface slope at a node (height change per pixel along x and y)
+    pub fn gradient(&self, col: usize, row: usize) -> (f32, f32) {
+        let left = self.height(col.saturating_sub(1), row);
+        let right = self.height((col + 1).min(self.cols - 1), row);
+        let down = self.height(col, row.saturating_sub(1));
+        let up = self.height(col, (row + 1).min(self.rows - 1));
+        let span = 2.0 * self.cell_size;
+        ((right - left) / span, (up - down) / span)
+    }
+
+    // Mark the nodes covered by obstacles as fixed walls
+    pub fn set_obstacles(&mut self, obstacles: &[Obstacle]) {
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                let i = row * self.cols + col;
+                let position = self.node_position(col, row);
+                self.solid[i] = obstacles.iter().any(|o| o.contains(position));
+                if self.solid[i] {
+                    self.current[i] = 0.0;
+                    self.previous[i] = 0.0;
+                }
+            }
+        }
+    }
+
+    // Push the surface down around `position` with a smooth Gaussian bump
+    pub fn impulse(&mut self, position: Point, strength: f32) {
+        let radius_cells = (IMPULSE_RADIUS / self.cell_size).max(1.0);
+        let col_center = (position.x - self.bounds.left) / self.cell_size;
+        let row_center = (position.y - self.bounds.bottom) / self.cell_size;
+        let reach = (radius_cells * 2.0).ceil() as i32;
+
+        for dr in -reach..=reach {
+            for dc in -reach..=reach {
+                let col = col_center.round() as i32 + dc;
+                let row = row_center.round() as i32 + dr;
+                // Leave the fixed border nodes alone
+                if col <= 0 || row <= 0 || col >= self.cols as i32 - 1 || row >= self.rows as i32 - 1 {
+                    continue;
+                }
+                if self.solid[row as usize * self.cols + col as usize] {
+                    continue;
+                }
+                let dx = col as f32 - col_center;
+                let dy = row as f32 - row_center;
+                let falloff = (-(dx * dx + dy * dy) / (radius_cells * radius_cells)).exp();
+                self.current[row as usize * self.cols + col as usize] -= strength * falloff;
+            }
+        }
+    }
+
+    // Advance the simulation by `dt` seconds using fixed solver steps
+    pub fn update(&mut self, dt: f32) {
+        self.accumulator = (self.accumulator + dt).min(SIM_STEP * MAX_STEPS_PER_UPDATE as f32);
+        while self.accumulator >= SIM_STEP {
+            self.step();
+            self.accumulator -= SIM_STEP;
+        }
+    }
+
+    // One explicit finite-difference step of the damped wave equation
+    fn step(&mut self) {
+        let c2 = COURANT * COURANT;
+        let decay = (-self.damping * SIM_STEP).exp();
+        let cols = self.cols;
+
+        for row in 1..self.rows - 1 {
+            for col in 1..cols - 1 {
+                let i = row * cols + col;
+                if self.solid[i] {
+                    continue;
+                }
+                let u = self.current[i];
+                let laplacian = self.current[i - 1] + self.current[i + 1]
+                    + self.current[i - cols] + self.current[i + cols]
+                    - 4.0 * u;
+                // Write the next state over the previous one, then swap below
+                self.previous[i] = (2.0 * u - self.previous[i] + c2 * laplacian) * decay;
+            }
+        }
+
+        std::mem::swap(&mut self.current, &mut self.previous);
+    }
+
+    // Flatten the surface
+    pub fn clear(&mut self) {
+        self.current.iter_mut().for_each(|h| *h = 0.0);
+        self.previous.iter_mut().for_each(|h| *h = 0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> WaveGrid {
+        WaveGrid::new(Bounds::from_size(200.0, 200.0), 5.0, 0.5)
+    }
+
+    fn heights(grid: &WaveGrid) -> Vec<f32> {
+        (0..grid.rows()).flat_map(|row| (0..grid.cols()).map(move |col| grid.height(col, row))).collect()
+    }
+
+    #[test]
+    fn impulses_push_the_surface_down() {
+        let mut grid = grid();
+        grid.impulse(Point::default(), IMPULSE_STRENGTH);
+        assert!(grid.height(20, 20) < -0.9 * IMPULSE_STRENGTH);
+        assert_eq!(grid.height(0, 20), 0.0, "border nodes stay fixed");
+    }
+
+    #[test]
+    fn updates_step_in_whole_solver_steps() {
+        let mut grid = grid();
+        grid.impulse(Point::default(), IMPULSE_STRENGTH);
+        let start = heights(&grid);
+        grid.update(SIM_STEP * 0.6);
+        assert_eq!(heights(&grid), start);
+        grid.update(SIM_STEP * 0.6);
+        assert_ne!(heights(&grid), start);
+    }
+
+    #[test]
+    fn slow_frames_catch_up_a_limited_number_of_steps() {
+        let (mut slow, mut capped) = (grid(), grid());
+        for grid in [&mut slow, &mut capped] {
+            grid.impulse(Point::default(), IMPULSE_STRENGTH);
+        }
+        slow.update(10.0);
+        capped.update(SIM_STEP * MAX_STEPS_PER_UPDATE as f32);
+        assert_eq!(heights(&slow), heights(&capped));
+    }
+
+    #[test]
+    fn waves_spread_out_and_die_down() {
+        let mut grid = grid();
+        grid.impulse(Point::default(), IMPULSE_STRENGTH);
+        let energy = |grid: &WaveGrid| heights(grid).iter().map(|h| h * h).sum::<f32>();
+        let start = energy(&grid);
+        assert_eq!(grid.height(32, 20), 0.0);
+        for _ in 0..60 {
+            grid.update(SIM_STEP);
+        }
+        assert_ne!(grid.height(32, 20), 0.0);
+        for _ in 0..600 {
+            grid.update(SIM_STEP);
+        }
+        assert!(energy(&grid) < 0.1 * start);
+    }
+
+    #[test]
+    fn obstacles_hold_their_nodes_flat() {
+        let mut grid = grid();
+        grid.set_obstacles(&[Obstacle::Circle { center: Point::new(30.0, 0.0), radius: 12.0 }]);
+        grid.impulse(Point::default(), IMPULSE_STRENGTH);
+        for _ in 0..60 {
+            grid.update(SIM_STEP);
+            assert_eq!(grid.height(26, 20), 0.0);
+        }
+        assert_ne!(grid.height(14, 20), 0.0);
+    }
+}
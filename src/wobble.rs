@@ -0,0 +1,116 @@
+use noise::{NoiseFn, Perlin};
+
+use crate::ripple::{WOBBLE_AMOUNT, WOBBLE_SPEED};
+
+// Noise wobble settings
+pub const WOBBLE_FREQUENCY: f32 = 1.5;      // Radius of the circle sampled in noise space (more bumps when larger)
+pub const NOISE_GAIN: f32 = 2.0;            // Perlin rarely leaves -0.5..0.5, so scale it to sine-like peaks
+pub const NOISE_DRIFT: f32 = 0.25;          // Noise units travelled per second at speed 1
+pub const RING_SEPARATION: f32 = 7.3;       // Noise space between rings, so each ring deforms on its own
+pub const NOISE_OFFSET_RANGE: f32 = 1000.0; // Spread of the per-ripple offsets into the noise
+
+// How ring outlines are deformed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WobbleMode {
+    #[default]
+    Sine,                                   // The same travelling sine on every ring
+    Noise,                                  // Smooth Perlin noise, different for every ring and ripple
+}
+
+impl WobbleMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            WobbleMode::Sine => "SINE",
+            WobbleMode::Noise => "NOISE",
+        }
+    }
+}
+
+// Wobble style and strength
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Wobble {
+    pub mode: WobbleMode,
+    pub amplitude: f32,                     // Peak radius change in percent
+    pub frequency: f32,                     // Bumps around a ring (noise mode)
+    pub speed: f32,                         // How fast the outline changes
+}
+
+impl Wobble {
+    pub const DEFAULT: Wobble = Wobble {
+        mode: WobbleMode::Sine,
+        amplitude: WOBBLE_AMOUNT,
+        frequency: WOBBLE_FREQUENCY,
+        speed: WOBBLE_SPEED,
+    };
+
+    // Relative radius change (0.01 = 1% larger) of `ring` at `degrees`
+    // around the center. `offset` keeps ripples from wobbling in step.
+    pub fn displacement(&self, noise: &Perlin, offset: f32, ring: i16, degrees: f32, time: f32) -> f32 {
+        match self.mode {
+            WobbleMode::Sine => self.amplitude * (time * self.speed + degrees / 30.0).sin() * 0.01,
+            WobbleMode::Noise => {
+                // Walk a circle through the noise so the outline closes seamlessly,
+                // with time moving the circle along the third axis
+                let (sin, cos) = degrees.to_radians().sin_cos();
+                let value = noise.get([
+                    (offset + cos * self.frequency) as f64,
+                    (ring as f32 * RING_SEPARATION + sin * self.frequency) as f64,
+                    (time * self.speed * NOISE_DRIFT) as f64,
+                ]) as f32;
+                self.amplitude * NOISE_GAIN * value * 0.01
+            }
+        }
+    }
+}
+
+impl Default for Wobble {
+    fn default() -> Self {
+        Wobble::DEFAULT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random;
+
+    const NOISE: Wobble = Wobble { mode: WobbleMode::Noise, amplitude: 5.0, ..Wobble::DEFAULT };
+
+    // Displacements around a ring, one per degree
+    fn around(wobble: &Wobble, noise: &Perlin, offset: f32, ring: i16, time: f32) -> Vec<f32> {
+        (0..=360).map(|degrees| wobble.displacement(noise, offset, ring, degrees as f32, time)).collect()
+    }
+
+    #[test]
+    fn noise_stays_within_the_amplitude() {
+        let noise = random::seeded_noise(3);
+        let bound = NOISE.amplitude * NOISE_GAIN * 0.01;
+        for ring in 0..4 {
+            for step in 0..20 {
+                let values = around(&NOISE, &noise, 12.5, ring, step as f32 * 0.37);
+                assert!(values.iter().all(|value| value.abs() <= bound));
+                assert!(values.iter().any(|&value| value != 0.0));
+            }
+        }
+    }
+
+    #[test]
+    fn noise_outlines_close_and_differ_per_ring() {
+        let noise = random::seeded_noise(3);
+        let first = around(&NOISE, &noise, 12.5, 0, 1.0);
+        assert!((first[0] - first[360]).abs() < 1e-6);
+        assert_ne!(first, around(&NOISE, &noise, 12.5, 1, 1.0));
+        assert_ne!(first, around(&NOISE, &noise, 40.0, 0, 1.0));
+        assert_ne!(first, around(&NOISE, &noise, 12.5, 0, 2.0), "noise drifts over time");
+    }
+
+    #[test]
+    fn sine_wobble_peaks_at_the_amplitude() {
+        let noise = random::seeded_noise(3);
+        let values = around(&Wobble::DEFAULT, &noise, 0.0, 0, 0.0);
+        let peak = values.iter().fold(0.0f32, |peak, value| peak.max(value.abs()));
+        assert!((peak - WOBBLE_AMOUNT * 0.01).abs() < 1e-4);
+        let flat = Wobble { amplitude: 0.0, ..NOISE };
+        assert!(around(&flat, &noise, 12.5, 0, 0.0).iter().all(|&value| value == 0.0));
+    }
+}